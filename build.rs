@@ -1,67 +1,792 @@
 use evgfx::convert;
-use fe_data::*;
+use maps::{Behavior, MapFile, Objective};
+use serde::Deserialize;
 use std::env;
 use std::error::Error;
 use std::fs;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use toml::Spanned;
 
-fn convert_image(
-	config: &convert::Config,
-	input_path: &str,
-	output_path: &PathBuf,
-	palette_path: &PathBuf,
-) -> Result<(), Box<dyn Error>> {
+#[path = "build/maps.rs"]
+mod maps;
+
+/// The image which maps are drawn with. Must match the one loaded by `GameState::enter`.
+const TILESET: &str = "tree_tiles";
+/// Longest chapter title or objective which fits in `ChapterIntro`'s window.
+const INTRO_WIDTH: usize = 26;
+
+/// Conversion settings for an image, read from a TOML file beside it (such as gfx/luvui.toml).
+/// Images without one use the defaults.
+#[derive(Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct ImageManifest {
+	/// Width and height of each tile, in pixels. The map's metatiles are 16x16.
+	tile_size: [u8; 2],
+	/// Red, green and blue of the color which is made transparent.
+	transparency: [u8; 3],
+	/// Bits per pixel. Only 4 is supported: the game draws every background and sprite in 16-color mode.
+	bpp: u8,
+	/// Images in the same group are given one shared palette, so that they can use the same palette bank.
+	palette_group: Option<String>,
+	/// Removes repeated tiles. `Image::tile` still finds each tile by its original index.
+	dedup: bool,
+}
+
+impl Default for ImageManifest {
+	fn default() -> Self {
+		Self {
+			tile_size: [8, 8],
+			transparency: [0xFF, 0x00, 0xFF],
+			bpp: 4,
+			palette_group: None,
+			dedup: false,
+		}
+	}
+}
+
+impl ImageManifest {
+	fn open(image: &str) -> Result<Self, Box<dyn Error>> {
+		let path = format!("src/assets/gfx/{image}.toml");
+		if !Path::new(&path).exists() {
+			return Ok(Self::default());
+		}
+		println!("cargo:rerun-if-changed={path}");
+		let manifest: Self = toml::from_str(&fs::read_to_string(&path)?).map_err(|err| format!("{path}: {err}"))?;
+		if !manifest.tile_size.iter().all(|size| [8, 16, 32, 64].contains(size)) {
+			return Err(format!("{path}: tile_size must be 8, 16, 32 or 64 pixels on each side").into());
+		}
+		if manifest.bpp != 4 {
+			return Err(format!(
+				"{path}: bpp = {} is not supported; backgrounds and sprites are all drawn in 16-color mode, so bpp must be 4",
+				manifest.bpp
+			)
+			.into());
+		}
+		Ok(manifest)
+	}
+
+	/// Size of each tile in bytes.
+	fn tile_bytes(&self) -> usize {
+		self.tile_size[0] as usize * self.tile_size[1] as usize / 2
+	}
+}
+
+/// An image's tiles and palette, as produced by the converter.
+/// Palettes leave out the transparent color, so color `i` is used by pixels with index `i + 1`.
+struct ConvertedImage {
+	tiles: Vec<u8>,
+	palette: Vec<u16>,
+	/// The index each tile ends up at once duplicates have been removed.
+	map: Vec<u16>,
+}
+
+fn convert_image(out_dir: &str, image: &str, manifest: &ImageManifest) -> Result<ConvertedImage, Box<dyn Error>> {
+	let input_path = format!("src/assets/gfx/{image}.png");
+	let tiles_path: PathBuf = [out_dir, "assets/gfx", &format!("{image}.4bpp")].iter().collect();
+	let palette_path: PathBuf = [out_dir, "assets/gfx", &format!("{image}.pal")].iter().collect();
 	println!("cargo:rerun-if-changed={input_path}");
-	fs::create_dir_all(output_path.parent().unwrap())?;
-	fs::create_dir_all(palette_path.parent().unwrap())?;
+	fs::create_dir_all(tiles_path.parent().unwrap())?;
 
-	let (palettes, tiles, _) = config.convert_image(input_path).unwrap();
+	let [red, green, blue] = manifest.transparency;
+	let config = convert::Config::new()
+		.with_tilesize(manifest.tile_size[0].into(), manifest.tile_size[1].into())
+		.with_transparency_color(red, green, blue);
+	let (palettes, tiles, _) = config.convert_image(&input_path).unwrap();
 
-	tiles.write_4bpp(output_path.to_str().unwrap()).unwrap();
+	tiles.write_4bpp(tiles_path.to_str().unwrap()).unwrap();
 	palettes.write_rgb555(palette_path.to_str().unwrap(), true).unwrap();
 
-	Ok(())
+	let tiles = fs::read(&tiles_path)?;
+	let palette = fs::read(&palette_path)?
+		.chunks_exact(2)
+		.map(|color| u16::from_le_bytes([color[0], color[1]]))
+		.collect();
+	let map = (0..tiles.len() / manifest.tile_bytes()).map(|i| i as u16).collect();
+	Ok(ConvertedImage { tiles, palette, map })
+}
+
+impl ConvertedImage {
+	/// Keeps only the first copy of each tile.
+	fn dedup(&mut self, tile_bytes: usize) {
+		let mut unique: Vec<&[u8]> = Vec::new();
+		for (i, tile) in self.tiles.chunks(tile_bytes).enumerate() {
+			self.map[i] = match unique.iter().position(|other| *other == tile) {
+				Some(index) => index,
+				None => {
+					unique.push(tile);
+					unique.len() - 1
+				}
+			} as u16;
+		}
+		self.tiles = unique.concat();
+	}
+
+	/// Switches the image over to a palette which holds all of its colors, redrawing its tiles to match.
+	fn use_palette(&mut self, palette: &[u16]) {
+		let remap: Vec<u8> = std::iter::once(0)
+			.chain(self.palette.iter().map(|color| 1 + palette.iter().position(|other| other == color).unwrap() as u8))
+			.collect();
+		for byte in &mut self.tiles {
+			*byte = remap[(*byte & 0xF) as usize] | remap[(*byte >> 4) as usize] << 4;
+		}
+		self.palette = palette.to_vec();
+	}
+
+	/// Writes the image out for `include_image!`.
+	fn write(&self, out_dir: &str, image: &str) -> Result<(), Box<dyn Error>> {
+		let path = |extension: &str| -> PathBuf { [out_dir, "assets/gfx", &format!("{image}.{extension}")].iter().collect() };
+		fs::write(path("4bpp"), &self.tiles)?;
+		fs::write(path("pal"), self.palette.iter().flat_map(|color| color.to_le_bytes()).collect::<Vec<u8>>())?;
+		fs::write(path("map"), self.map.iter().flat_map(|index| index.to_le_bytes()).collect::<Vec<u8>>())?;
+		Ok(())
+	}
+}
+
+/// Converts every image, following its manifest. Images in a palette group are converted first,
+/// then given the group's combined palette.
+fn convert_images(out_dir: &str, images: &[String]) -> Result<Vec<(ImageManifest, ConvertedImage)>, Box<dyn Error>> {
+	let mut manifests = Vec::new();
+	let mut converted = Vec::new();
+	for image in images {
+		let manifest = ImageManifest::open(image)?;
+		let mut result = convert_image(out_dir, image, &manifest)?;
+		if manifest.dedup {
+			result.dedup(manifest.tile_bytes());
+		}
+		manifests.push(manifest);
+		converted.push(result);
+	}
+
+	let mut groups: Vec<&str> = manifests.iter().filter_map(|manifest| manifest.palette_group.as_deref()).collect();
+	groups.sort();
+	groups.dedup();
+	for group in groups {
+		let members: Vec<usize> = (0..images.len())
+			.filter(|i| manifests[*i].palette_group.as_deref() == Some(group))
+			.collect();
+		let mut palette: Vec<u16> = Vec::new();
+		for i in &members {
+			for color in &converted[*i].palette {
+				if !palette.contains(color) {
+					palette.push(*color);
+				}
+			}
+		}
+		if palette.len() > 15 {
+			return Err(format!(
+				"palette group {group:?} needs {} colors, but a palette bank only has room for 15",
+				palette.len()
+			)
+			.into());
+		}
+		for i in members {
+			converted[i].use_palette(&palette);
+		}
+	}
+
+	for (image, result) in images.iter().zip(&converted) {
+		result.write(out_dir, image)?;
+	}
+	Ok(manifests.into_iter().zip(converted).collect())
+}
+
+/// Lists every file under `directory` with the given extension, searching subdirectories too.
+/// Names are relative to `directory`, use '/' as a separator, have no extension, and are sorted.
+fn find_assets(directory: &str, extension: &str) -> Result<Vec<String>, Box<dyn Error>> {
+	fn walk(root: &Path, directory: &Path, extension: &str, names: &mut Vec<String>) -> Result<(), Box<dyn Error>> {
+		// Adding or removing a file changes its directory, so new assets are picked up.
+		println!("cargo:rerun-if-changed={}", directory.display());
+		for entry in fs::read_dir(directory)? {
+			let path = entry?.path();
+			if path.is_dir() {
+				walk(root, &path, extension, names)?;
+			} else if path.extension().is_some_and(|ext| ext == extension) {
+				let name = path.strip_prefix(root)?.with_extension("");
+				let name = name.components().map(|part| part.as_os_str().to_string_lossy()).collect::<Vec<_>>();
+				names.push(name.join("/"));
+			}
+		}
+		Ok(())
+	}
+
+	let mut names = Vec::new();
+	walk(Path::new(directory), Path::new(directory), extension, &mut names)?;
+	names.sort();
+	Ok(names)
+}
+
+/// Converts an asset's name into the name of its constant, such as "Debug Map" to "DEBUG_MAP".
+fn to_const_name(name: &str) -> String {
+	name.split(|c: char| !c.is_ascii_alphanumeric())
+		.filter(|word| !word.is_empty())
+		.map(|word| word.to_ascii_uppercase())
+		.collect::<Vec<_>>()
+		.join("_")
+}
+
+/// Generates a module with one constant per asset, as made by `constant`.
+/// Fails if two assets would share a constant.
+fn asset_module(
+	directory: &str,
+	kind: &str,
+	names: &[String],
+	constant: impl Fn(&str) -> String,
+) -> Result<String, Box<dyn Error>> {
+	let mut module = format!("/// Everything in src/assets/{directory}.\npub mod {directory} {{\n");
+	let mut constants: Vec<String> = Vec::new();
+	for name in names {
+		let const_name = to_const_name(name);
+		if const_name.is_empty() || const_name.starts_with(|c: char| c.is_ascii_digit()) {
+			return Err(format!("src/assets/{directory}/{name}: cannot be named as a constant").into());
+		}
+		if constants.contains(&const_name) {
+			return Err(format!("src/assets/{directory}/{name}: another asset is also named {const_name}").into());
+		}
+		module += &format!("\tpub const {const_name}: {kind} = {};\n", constant(name));
+		constants.push(const_name);
+	}
+	module += "}\n";
+	Ok(module)
+}
+
+#[derive(Default, Deserialize)]
+struct TerrainCosts {
+	foot: Option<u8>,
+	armor: Option<u8>,
+	mounted: Option<u8>,
+	flying: Option<u8>,
+}
+
+#[derive(Deserialize)]
+struct TerrainEntry {
+	tiles: Vec<u8>,
+	#[serde(default)]
+	cost: TerrainCosts,
+	#[serde(default)]
+	defense: i8,
+	#[serde(default)]
+	avoid: i8,
+	#[serde(default)]
+	heal: u8,
+}
+
+/// Returns the number of tileset indices which have a terrain.
+fn compile_terrain(input_path: &str, output_path: &PathBuf) -> Result<usize, Box<dyn Error>> {
+	println!("cargo:rerun-if-changed={input_path}");
+
+	let table: toml::value::Table = toml::from_str(&fs::read_to_string(input_path)?)?;
+	let mut terrain = String::new();
+	// Maps each tileset index to the terrain which uses it.
+	let mut tiles: Vec<Option<usize>> = Vec::new();
+
+	for (id, (name, entry)) in table.into_iter().enumerate() {
+		let entry: TerrainEntry = entry.try_into()?;
+		let cost = &entry.cost;
+		terrain += &format!(
+			"\t\tcrate::terrain::Terrain {{ name: {name:?}, movement_cost: [{:?}, {:?}, {:?}, {:?}], defense: {}, avoid: {}, heal: {} }},\n",
+			cost.foot, cost.armor, cost.mounted, cost.flying, entry.defense, entry.avoid, entry.heal,
+		);
+
+		for tile in entry.tiles {
+			let tile = tile as usize;
+			if tiles.len() <= tile {
+				tiles.resize(tile + 1, None);
+			}
+			if tiles[tile].is_some() {
+				return Err(format!("{input_path}: tile {tile} is assigned to more than one terrain").into());
+			}
+			tiles[tile] = Some(id);
+		}
+	}
+
+	let tile_count = tiles.len();
+	let tiles = tiles
+		.iter()
+		.enumerate()
+		.map(|(tile, id)| match id {
+			Some(id) => Ok(id.to_string()),
+			None => Err(format!("{input_path}: tile {tile} has no terrain")),
+		})
+		.collect::<Result<Vec<String>, String>>()?
+		.join(", ");
+
+	fs::create_dir_all(output_path.parent().unwrap())?;
+	fs::write(
+		output_path,
+		format!("crate::terrain::TerrainTable {{\n\tterrain: &[\n{terrain}\t],\n\ttiles: &[{tiles}],\n}}\n"),
+	)?;
+
+	Ok(tile_count)
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct StatBlock {
+	#[serde(default)]
+	hp: u8,
+	#[serde(default)]
+	strength: u8,
+	#[serde(default)]
+	magic: u8,
+	#[serde(default)]
+	skill: u8,
+	#[serde(default)]
+	speed: u8,
+	#[serde(default)]
+	luck: u8,
+	#[serde(default)]
+	defense: u8,
+	#[serde(default)]
+	resistance: u8,
+}
+
+impl StatBlock {
+	fn to_engine(&self) -> String {
+		format!(
+			"crate::stats::Stats {{ hp: {}, strength: {}, magic: {}, skill: {}, speed: {}, luck: {}, defense: {}, resistance: {} }}",
+			self.hp, self.strength, self.magic, self.skill, self.speed, self.luck, self.defense, self.resistance,
+		)
+	}
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum MovementClass {
+	Foot,
+	Armor,
+	Mounted,
+	Flying,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum WeaponKind {
+	Sword,
+	Lance,
+	Axe,
+	Bow,
+	Anima,
+	Light,
+	Dark,
+	Staff,
+}
+
+#[derive(Deserialize)]
+struct ClassEntry {
+	#[serde(default)]
+	desc: String,
+	movement: MovementClass,
+	#[serde(rename = "move")]
+	movement_points: u8,
+	sprite: String,
+	#[serde(default)]
+	icon: u8,
+	#[serde(default)]
+	weapons: Vec<WeaponKind>,
+	#[serde(default)]
+	bases: StatBlock,
+	#[serde(default)]
+	growths: StatBlock,
+	#[serde(default)]
+	caps: StatBlock,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum ItemKind {
+	Sword,
+	Lance,
+	Axe,
+	Bow,
+	Anima,
+	Light,
+	Dark,
+	Staff,
+	Consumable,
+}
+
+impl ItemKind {
+	fn to_engine(&self) -> String {
+		match self {
+			ItemKind::Staff => "crate::item::ItemKind::Staff".to_string(),
+			ItemKind::Consumable => "crate::item::ItemKind::Consumable".to_string(),
+			weapon => format!("crate::item::ItemKind::Weapon(crate::combat::WeaponKind::{weapon:?})"),
+		}
+	}
+}
+
+#[derive(Deserialize)]
+struct ItemEntry {
+	kind: ItemKind,
+	uses: u8,
+	#[serde(default)]
+	might: u8,
+	#[serde(default)]
+	hit: u8,
+	#[serde(default)]
+	crit: u8,
+	#[serde(default)]
+	weight: u8,
+	#[serde(default)]
+	range: [u8; 2],
+	#[serde(default)]
+	heal: u8,
 }
 
-macro_rules! make_image {
-	($config:expr, $resource:expr) => {
-		convert_image(
-			$config,
-			concat!("src/assets/", $resource, ".png"),
-			&[
-				&env::var("OUT_DIR")?,
-				concat!("assets/", $resource, ".4bpp"),
-			]
+/// Compiles the item database and returns the name of each item, in ItemId order.
+fn compile_items(input_path: &str, output_path: &PathBuf) -> Result<Vec<String>, Box<dyn Error>> {
+	println!("cargo:rerun-if-changed={input_path}");
+
+	let table: toml::value::Table = toml::from_str(&fs::read_to_string(input_path)?)?;
+	let mut names = Vec::new();
+	let mut variants = String::new();
+	let mut ids = String::new();
+	let mut items = String::new();
+
+	for (name, entry) in table {
+		let entry: ItemEntry = entry
+			.try_into()
+			.map_err(|err| format!("{input_path}: item {name:?}: {err}"))?;
+		let variant = to_type_name(&name);
+		if variant.is_empty() || variant.starts_with(|c: char| c.is_ascii_digit()) {
+			return Err(format!("{input_path}: {name:?} is not a valid item name").into());
+		}
+		if entry.uses == 0 {
+			return Err(format!("{input_path}: item {name:?} must have at least one use").into());
+		}
+		if entry.range[0] > entry.range[1] {
+			return Err(format!("{input_path}: item {name:?} has a minimum range above its maximum").into());
+		}
+
+		variants += &format!("\t{variant},\n");
+		ids += &format!("ItemId::{variant}, ");
+		items += &format!(
+			"\tcrate::item::ItemData {{ name: {name:?}, kind: {}, uses: {}, might: {}, hit: {}, crit: {}, weight: {}, min_range: {}, max_range: {}, heal: {} }},\n",
+			entry.kind.to_engine(),
+			entry.uses,
+			entry.might,
+			entry.hit,
+			entry.crit,
+			entry.weight,
+			entry.range[0],
+			entry.range[1],
+			entry.heal,
+		);
+		names.push(name);
+	}
+
+	fs::create_dir_all(output_path.parent().unwrap())?;
+	fs::write(
+		output_path,
+		format!(
+			"#[derive(Clone, Copy, PartialEq, Eq, Debug)]\n\
+			pub enum ItemId {{\n{variants}}}\n\n\
+			pub const ITEM_COUNT: usize = {};\n\n\
+			impl ItemId {{\n\tpub const ALL: [ItemId; ITEM_COUNT] = [{ids}];\n}}\n\n\
+			pub static ITEMS: [ItemData; ITEM_COUNT] = [\n{items}];\n",
+			names.len(),
+		),
+	)?;
+
+	Ok(names)
+}
+
+/// Converts a name from a data file into a Rust type name, such as "Pegasus Knight" to "PegasusKnight".
+fn to_type_name(name: &str) -> String {
+	name.split(|c: char| !c.is_ascii_alphanumeric())
+		.filter(|word| !word.is_empty())
+		.map(|word| word[..1].to_ascii_uppercase() + &word[1..])
+		.collect()
+}
+
+/// Compiles the class database and returns the name of each class, in ClassId order.
+fn compile_classes(
+	input_path: &str,
+	output_path: &PathBuf,
+	images: &[String],
+) -> Result<Vec<String>, Box<dyn Error>> {
+	println!("cargo:rerun-if-changed={input_path}");
+
+	let table: toml::value::Table = toml::from_str(&fs::read_to_string(input_path)?)?;
+	let mut names = Vec::new();
+	let mut variants = String::new();
+	let mut ids = String::new();
+	let mut classes = String::new();
+
+	for (name, entry) in table {
+		let entry: ClassEntry = entry
+			.try_into()
+			.map_err(|err| format!("{input_path}: class {name:?}: {err}"))?;
+		let variant = to_type_name(&name);
+		if variant.is_empty() || variant.starts_with(|c: char| c.is_ascii_digit()) {
+			return Err(format!("{input_path}: {name:?} is not a valid class name").into());
+		}
+		let sprite = entry.sprite.strip_prefix("gfx/");
+		let Some(sprite) = sprite.filter(|sprite| images.iter().any(|image| image == sprite)) else {
+			return Err(format!("{input_path}: class {name:?} uses missing sprite {:?}", entry.sprite).into());
+		};
+
+		let weapons = entry
+			.weapons
 			.iter()
-			.collect(),
-			&[
-				&env::var("OUT_DIR")?,
-				concat!("assets/", $resource, ".pal"),
-			]
+			.map(|kind| format!("crate::combat::WeaponKind::{kind:?}"))
+			.collect::<Vec<String>>()
+			.join(", ");
+
+		variants += &format!("\t{variant},\n");
+		ids += &format!("ClassId::{variant}, ");
+		classes += &format!(
+			"\tcrate::class::ClassData {{\n\
+			\t\tname: {name:?},\n\
+			\t\tdescription: {:?},\n\
+			\t\tbases: {},\n\
+			\t\tgrowths: {},\n\
+			\t\tcaps: {},\n\
+			\t\tmovement_class: crate::terrain::MovementClass::{:?},\n\
+			\t\tmovement: {},\n\
+			\t\tweapons: &[{weapons}],\n\
+			\t\tsprite: crate::assets::gfx::{},\n\
+			\t\ticon: {},\n\
+			\t}},\n",
+			entry.desc,
+			entry.bases.to_engine(),
+			entry.growths.to_engine(),
+			entry.caps.to_engine(),
+			entry.movement,
+			entry.movement_points,
+			to_const_name(sprite),
+			entry.icon,
+		);
+		names.push(name);
+	}
+
+	fs::create_dir_all(output_path.parent().unwrap())?;
+	fs::write(
+		output_path,
+		format!(
+			"#[derive(Clone, Copy, PartialEq, Eq, Debug)]\n\
+			pub enum ClassId {{\n{variants}}}\n\n\
+			pub const CLASS_COUNT: usize = {};\n\n\
+			impl ClassId {{\n\tpub const ALL: [ClassId; CLASS_COUNT] = [{ids}];\n}}\n\n\
+			pub static CLASSES: [ClassData; CLASS_COUNT] = [\n{classes}];\n",
+			names.len(),
+		),
+	)?;
+
+	Ok(names)
+}
+
+impl Objective {
+	fn to_engine(&self) -> String {
+		match self {
+			Objective::Rout => "crate::objective::Objective::Rout".to_string(),
+			Objective::DefeatBoss => "crate::objective::Objective::DefeatBoss".to_string(),
+			Objective::Seize { x, y } => format!(
+				"crate::objective::Objective::Seize(crate::transform::Vector2D {{ x: {x}, y: {y} }})"
+			),
+			Objective::Survive { turns } => format!("crate::objective::Objective::Survive({turns})"),
+			Objective::Escape { x, y } => format!(
+				"crate::objective::Objective::Escape(crate::transform::Vector2D {{ x: {x}, y: {y} }})"
+			),
+		}
+	}
+}
+
+impl MapFile {
+	/// Generates a Rust expression which constructs the map's LevelData. The map must have been validated.
+	fn to_engine(&self) -> String {
+		let mut map = String::new();
+		for row in self.data.chunks(self.width() as usize) {
+			map += "\t\t";
+			for tile in row {
+				map += &format!("{}, ", tile.get_ref());
+			}
+			map += "\n";
+		}
+
+		let mut units = String::new();
+		for unit in &self.units {
+			let unit_items: Vec<String> = unit
+				.items
+				.iter()
+				.map(|item| format!("crate::item::ItemId::{}", to_type_name(item)))
+				.collect();
+			units += &format!(
+				"\t\tcrate::game::UnitData {{ name: {:?}, class: crate::class::ClassId::{}, x: {}, y: {}, is_boss: {}, level: {}, faction: crate::unit::Faction::{:?}, items: &[{}], behavior: crate::ai::Behavior::{:?} }},\n",
+				unit.name.get_ref(), to_type_name(&unit.class), unit.x, unit.y, unit.boss, unit.level, unit.faction, unit_items.join(", "),
+				unit.behavior.unwrap_or(if unit.boss { Behavior::Guard } else { Behavior::Aggressive }),
+			);
+		}
+
+		let spawns: Vec<String> = self
+			.spawns
 			.iter()
-			.collect(),
-		)?;
-	};
+			.map(|spawn| format!("crate::transform::Vector2D {{ x: {}, y: {} }}", spawn.x(), spawn.y))
+			.collect();
+
+		format!(
+			"crate::game::LevelData {{\n\twidth: {},\n\theight: {},\n\tmap: &[\n{map}\t],\n\tunits: &[\n{units}\t],\n\
+			\tspawns: &[{}],\n\tobjective: {},\n\tdefeat: crate::objective::Defeat {{ lord: {:?}, turn_limit: {:?} }},\n}}\n",
+			self.width(),
+			self.height(),
+			spawns.join(", "),
+			self.objective.to_engine(),
+			self.defeat.lord.as_ref().map(Spanned::get_ref),
+			self.defeat.turn_limit.as_ref().map(Spanned::get_ref),
+		)
+	}
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct CampaignChapter {
+	title: String,
+	map: String,
+	objective: String,
+	next: Option<String>,
+}
+
+/// Compiles the list of chapters. Chapters are identified by their table's name, and `next` must name another one.
+/// Starting from the first, following `next` must visit every chapter once.
+fn compile_campaign(input_path: &str, output_path: &PathBuf, maps: &[String]) -> Result<(), Box<dyn Error>> {
+	println!("cargo:rerun-if-changed={input_path}");
+
+	let table: toml::value::Table = toml::from_str(&fs::read_to_string(input_path)?)?;
+	let mut ids = Vec::new();
+	let mut chapters = Vec::new();
+	for (id, entry) in table.into_iter() {
+		let chapter: CampaignChapter = entry.try_into().map_err(|err| format!("{input_path}: chapter {id:?}: {err}"))?;
+		if !maps.contains(&chapter.map) {
+			return Err(format!("{input_path}: chapter {id:?} uses unknown map {:?}", chapter.map).into());
+		}
+		for (field, text) in [("title", &chapter.title), ("objective", &chapter.objective)] {
+			if text.len() > INTRO_WIDTH || !text.chars().all(|c| (' '..='~').contains(&c)) {
+				return Err(format!(
+					"{input_path}: chapter {id:?}'s {field} must be printable ASCII and at most {INTRO_WIDTH} characters long"
+				)
+				.into());
+			}
+		}
+		ids.push(id);
+		chapters.push(chapter);
+	}
+	if chapters.is_empty() {
+		return Err(format!("{input_path}: the campaign has no chapters").into());
+	}
+	if chapters.len() > u8::MAX as usize + 1 {
+		return Err(format!("{input_path}: the campaign has more than {} chapters", u8::MAX as usize + 1).into());
+	}
+
+	let mut next = Vec::new();
+	for (id, chapter) in ids.iter().zip(&chapters) {
+		next.push(match &chapter.next {
+			Some(name) => Some(
+				ids.iter()
+					.position(|other| other == name)
+					.ok_or(format!("{input_path}: chapter {id:?} is followed by unknown chapter {name:?}"))?,
+			),
+			None => None,
+		});
+	}
+
+	let mut played = vec![false; chapters.len()];
+	let mut current = Some(0);
+	while let Some(i) = current {
+		if played[i] {
+			return Err(format!("{input_path}: chapter {:?} is played more than once", ids[i]).into());
+		}
+		played[i] = true;
+		current = next[i];
+	}
+	if let Some(i) = played.iter().position(|played| !played) {
+		return Err(format!("{input_path}: chapter {:?} is never played", ids[i]).into());
+	}
+
+	let mut output = String::from("[\n");
+	for (chapter, next) in chapters.iter().zip(next) {
+		output += &format!(
+			"\tcrate::campaign::Chapter {{ title: {:?}, map: &crate::assets::maps::{}, objective: {:?}, next: {:?} }},\n",
+			chapter.title,
+			to_const_name(&chapter.map),
+			chapter.objective,
+			next.map(|next| next as u8),
+		);
+	}
+	output += "]\n";
+
+	fs::create_dir_all(output_path.parent().unwrap())?;
+	fs::write(output_path, output)?;
+	Ok(())
 }
 
 fn main() -> Result<(), Box<dyn Error>> {
-	let config = convert::Config::new()
-		.with_tilesize(16, 16)
-		.with_transparency_color(0xFF, 0x00, 0xFF);
+	let out_dir = env::var("OUT_DIR")?;
 
-	make_image!(&config, "gfx/luvui");
-	make_image!(&config, "gfx/tree_tiles");
+	let images = find_assets("src/assets/gfx", "png")?;
+	let converted = convert_images(&out_dir, &images)?;
 
-	let config = convert::Config::new()
-		.with_transparency_color(0xFF, 0x00, 0xFF);
+	let terrain_tiles = compile_terrain(
+		"src/assets/terrain.toml",
+		&[&out_dir, "assets/terrain.rs"].iter().collect(),
+	)?;
+
+	let classes = compile_classes(
+		"src/assets/classes.toml",
+		&[&out_dir, "assets/classes.rs"].iter().collect(),
+		&images,
+	)?;
+
+	let items = compile_items(
+		"src/assets/items.toml",
+		&[&out_dir, "assets/items.rs"].iter().collect(),
+	)?;
+
+	// Every tile in a map must be drawable and have a terrain.
+	let tileset = images
+		.iter()
+		.position(|image| image == TILESET)
+		.ok_or(format!("the tileset, gfx/{TILESET}.png, is missing"))?;
+	let tile_count = converted[tileset].1.map.len().min(terrain_tiles);
+
+	let maps = find_assets("src/assets/maps", "toml")?;
+	// Every unit's name, so that saved units can be found again.
+	let mut names: Vec<String> = Vec::new();
+	for name in &maps {
+		let level = MapFile::open("src/assets/maps/", name)?;
+		level.validate(tile_count, &classes, &items)?;
+		for unit in &level.units {
+			if !names.contains(unit.name.get_ref()) {
+				names.push(unit.name.get_ref().clone());
+			}
+		}
+		let outpath: PathBuf = [&out_dir, "assets/maps", &format!("{name}.rs")].iter().collect();
+		fs::create_dir_all(outpath.parent().unwrap())?;
+		fs::write(outpath, level.to_engine())?;
+	}
+
+	fs::write(
+		[&out_dir, "assets/names.rs"].iter().collect::<PathBuf>(),
+		format!("[{}]\n", names.iter().map(|name| format!("{name:?}")).collect::<Vec<_>>().join(", ")),
+	)?;
 
-	make_image!(&config, "gfx/cursor");
+	compile_campaign(
+		"src/assets/campaign.toml",
+		&[&out_dir, "assets/campaign.rs"].iter().collect(),
+		&maps,
+	)?;
 
-	let level = MapData::open("src/assets/maps/", "Debug Map".to_string())?;
-	println!("cargo:rerun-if-changed=src/assets/maps/Debug Map.toml");
-	let outpath: PathBuf = [&env::var("OUT_DIR")?, "assets/maps/", "Debug Map.rs"].iter().collect();
-	fs::create_dir_all(outpath.parent().unwrap())?;
-	fs::write(outpath, level.to_engine()?)?;
+	let module = asset_module("gfx", "crate::tools::Image", &images, |name| {
+		let (manifest, _) = &converted[images.iter().position(|image| image == name).unwrap()];
+		format!(
+			"crate::tools::include_image!({:?}, {}, {}, {:?})",
+			format!("gfx/{name}"),
+			manifest.tile_size[0],
+			manifest.tile_size[1],
+			manifest.palette_group,
+		)
+	})? + &asset_module("maps", "crate::game::LevelData<'static>", &maps, |name| {
+		format!("crate::tools::include_resource!({:?})", format!("maps/{name}.rs"))
+	})?;
+	fs::write([&out_dir, "assets/assets.rs"].iter().collect::<PathBuf>(), module)?;
 
 	Ok(())
 }
@@ -0,0 +1,309 @@
+//! Parsing and validation of the map files in src/assets/maps. The build script generates code from them,
+//! and tests/maps.rs checks the validation.
+
+use serde::Deserialize;
+use std::error::Error;
+use std::fs;
+use toml::Spanned;
+
+/// Must match `item::INVENTORY_SIZE`.
+pub const INVENTORY_SIZE: usize = 5;
+/// Must match `movement::MAX_MAP_WIDTH` and `movement::MAX_MAP_HEIGHT`.
+pub const MAX_MAP_WIDTH: u16 = 32;
+pub const MAX_MAP_HEIGHT: u16 = 32;
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Faction {
+	#[default]
+	Player,
+	Enemy,
+	Other,
+}
+
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Behavior {
+	Aggressive,
+	InRange,
+	Guard,
+	Retreat,
+}
+
+#[derive(Deserialize)]
+pub struct MapUnit {
+	/// Spanned so that errors can point at the unit. toml doesn't record spans for whole tables.
+	pub name: Spanned<String>,
+	pub class: String,
+	pub x: u16,
+	pub y: u16,
+	#[serde(default = "default_level")]
+	pub level: u8,
+	#[serde(default)]
+	pub boss: bool,
+	#[serde(default)]
+	pub faction: Faction,
+	#[serde(default)]
+	pub items: Vec<String>,
+	/// How the unit acts when it isn't controlled by the player.
+	/// Bosses guard their tile unless told otherwise; everyone else is aggressive.
+	pub behavior: Option<Behavior>,
+}
+
+fn default_level() -> u8 {
+	1
+}
+
+/// What the player must do to clear a map.
+#[derive(Debug, Default, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Objective {
+	/// Defeat every enemy.
+	#[default]
+	Rout,
+	/// Defeat every enemy boss.
+	DefeatBoss,
+	/// Move the lord (or anyone, if there is no lord) onto a tile.
+	Seize { x: u16, y: u16 },
+	/// Hold out until the given turn is over.
+	Survive { turns: u16 },
+	/// Get everyone, or just the lord, off the map through a tile.
+	Escape { x: u16, y: u16 },
+}
+
+/// Ways to lose a map, besides losing every player unit.
+#[derive(Default, Deserialize)]
+pub struct Defeat {
+	/// Name of a player unit who must survive.
+	pub lord: Option<Spanned<String>>,
+	/// The map is lost once this turn is over.
+	pub turn_limit: Option<Spanned<u16>>,
+}
+
+/// A tile on the map.
+#[derive(Deserialize, PartialEq)]
+pub struct MapPosition {
+	/// Spanned so that errors can point at the position. Spans are ignored when comparing.
+	pub x: Spanned<u16>,
+	pub y: u16,
+}
+
+impl MapPosition {
+	pub fn x(&self) -> u16 {
+		*self.x.get_ref()
+	}
+}
+
+/// A map file from src/assets/maps.
+///
+/// These used to be read by fe-data. They are parsed here instead, because nearly every map feature
+/// (classes, items, factions, objectives) needs a new field checked against the rest of the game's data.
+/// The original format (`width`, `height`, `data`, `units` and `spawns`) is still accepted unchanged,
+/// and every field added since is optional.
+#[derive(Deserialize)]
+pub struct MapFile {
+	pub width: Spanned<u16>,
+	pub height: Spanned<u16>,
+	pub data: Vec<Spanned<u8>>,
+	#[serde(default)]
+	pub units: Vec<MapUnit>,
+	/// Tiles where the player may place their units before the map begins.
+	#[serde(default)]
+	pub spawns: Vec<MapPosition>,
+	#[serde(default)]
+	pub objective: Objective,
+	#[serde(default)]
+	pub defeat: Defeat,
+	#[serde(skip)]
+	pub path: String,
+	/// The file's text, kept to turn spans into line numbers for diagnostics.
+	#[serde(skip)]
+	source: String,
+}
+
+/// Collects everything wrong with a map, so that they can all be reported at once.
+struct MapDiagnostics<'m> {
+	map: &'m MapFile,
+	errors: Vec<String>,
+}
+
+impl MapDiagnostics<'_> {
+	/// Reports an error on the line holding the byte offset `at`, if there is one.
+	fn error(&mut self, at: Option<usize>, message: String) {
+		let path = &self.map.path;
+		self.errors.push(match at {
+			Some(at) => format!("{path}:{}: {message}", self.map.line_at(at)),
+			None => format!("{path}: {message}"),
+		});
+	}
+
+	fn into_result(self) -> Result<(), Box<dyn Error>> {
+		if self.errors.is_empty() {
+			Ok(())
+		} else {
+			Err(self.errors.join("\n").into())
+		}
+	}
+}
+
+impl MapFile {
+	pub fn open(directory: &str, name: &str) -> Result<Self, Box<dyn Error>> {
+		let path = format!("{directory}{name}.toml");
+		println!("cargo:rerun-if-changed={path}");
+		let source = fs::read_to_string(&path)?;
+		Self::parse(path, source)
+	}
+
+	/// Parses the text of a map. `path` is only used in errors.
+	pub fn parse(path: String, source: String) -> Result<Self, Box<dyn Error>> {
+		// toml's errors already say which line they are on.
+		let mut map: Self = toml::from_str(&source).map_err(|err| format!("{path}: {err}"))?;
+		map.path = path;
+		map.source = source;
+		Ok(map)
+	}
+
+	pub fn width(&self) -> u16 {
+		*self.width.get_ref()
+	}
+
+	pub fn height(&self) -> u16 {
+		*self.height.get_ref()
+	}
+
+	fn line_at(&self, offset: usize) -> usize {
+		self.source[..offset].matches('\n').count() + 1
+	}
+
+	fn contains(&self, x: u16, y: u16) -> bool {
+		x < self.width() && y < self.height()
+	}
+
+	/// Checks the map against itself and the rest of the game's data, so that mistakes fail the build
+	/// rather than misbehaving at runtime. `tile_count` is the number of tiles which are both in the
+	/// tileset and have a terrain.
+	///
+	/// Errors give the line of the value at fault. The objective is an internally tagged table, which toml
+	/// can't record spans through, so its errors only give the file.
+	pub fn validate(&self, tile_count: usize, classes: &[String], items: &[String]) -> Result<(), Box<dyn Error>> {
+		let mut diagnostics = MapDiagnostics { map: self, errors: Vec::new() };
+		let (width, height) = (self.width(), self.height());
+
+		if width == 0 || height == 0 {
+			diagnostics.error(Some(self.width.start()), format!("the map is {width}x{height}"));
+		}
+		if width > MAX_MAP_WIDTH {
+			diagnostics.error(
+				Some(self.width.start()),
+				format!("the map is {width} tiles wide, but can be at most {MAX_MAP_WIDTH}"),
+			);
+		}
+		if height > MAX_MAP_HEIGHT {
+			diagnostics.error(
+				Some(self.height.start()),
+				format!("the map is {height} tiles high, but can be at most {MAX_MAP_HEIGHT}"),
+			);
+		}
+		let expected = width as usize * height as usize;
+		if self.data.len() != expected {
+			diagnostics.error(
+				self.data.first().map(Spanned::start),
+				format!("data has {} tiles, but a {width}x{height} map needs {expected}", self.data.len()),
+			);
+		}
+		for (i, tile) in self.data.iter().enumerate() {
+			if *tile.get_ref() as usize >= tile_count {
+				diagnostics.error(
+					Some(tile.start()),
+					format!(
+						"tile {} at ({}, {}) is not in the tileset, which has {tile_count} tiles",
+						tile.get_ref(),
+						i % width.max(1) as usize,
+						i / width.max(1) as usize,
+					),
+				);
+			}
+		}
+
+		for (i, unit) in self.units.iter().enumerate() {
+			let line = Some(unit.name.start());
+			let name = unit.name.get_ref();
+			if !classes.contains(&unit.class) {
+				diagnostics.error(line, format!("unit {name:?} has unknown class {:?}", unit.class));
+			}
+			if unit.items.len() > INVENTORY_SIZE {
+				diagnostics.error(line, format!("unit {name:?} carries more than {INVENTORY_SIZE} items"));
+			}
+			for item in &unit.items {
+				if !items.contains(item) {
+					diagnostics.error(line, format!("unit {name:?} carries unknown item {item:?}"));
+				}
+			}
+			if !self.contains(unit.x, unit.y) {
+				diagnostics.error(line, format!("unit {name:?} at ({}, {}) is outside of the map", unit.x, unit.y));
+			}
+			if let Some(other) = self.units[..i].iter().find(|other| (other.x, other.y) == (unit.x, unit.y)) {
+				diagnostics.error(
+					line,
+					format!(
+						"unit {name:?} is on the same tile as {:?}, at ({}, {})",
+						other.name.get_ref(),
+						unit.x,
+						unit.y
+					),
+				);
+			}
+		}
+
+		for (i, spawn) in self.spawns.iter().enumerate() {
+			let line = Some(spawn.x.start());
+			let (x, y) = (spawn.x(), spawn.y);
+			if !self.contains(x, y) {
+				diagnostics.error(line, format!("spawn ({x}, {y}) is outside of the map"));
+			}
+			if self.spawns[..i].contains(spawn) {
+				diagnostics.error(line, format!("spawn ({x}, {y}) is listed twice"));
+			}
+			if let Some(unit) = self.units.iter().find(|unit| (unit.x, unit.y) == (x, y)) {
+				diagnostics.error(line, format!("spawn ({x}, {y}) is taken by {:?}", unit.name.get_ref()));
+			}
+		}
+
+		if let Objective::Seize { x, y } | Objective::Escape { x, y } = self.objective {
+			if !self.contains(x, y) {
+				diagnostics.error(None, format!("objective tile ({x}, {y}) is outside of the map"));
+			}
+		}
+		match self.objective {
+			Objective::DefeatBoss => {
+				if !self.units.iter().any(|unit| unit.boss && matches!(unit.faction, Faction::Enemy)) {
+					diagnostics.error(
+						None,
+						"the objective is to defeat the boss, but no enemy unit has `boss = true`".to_string(),
+					);
+				}
+			}
+			Objective::Survive { turns: 0 } => {
+				diagnostics.error(
+					None,
+					"the objective is to survive for 0 turns, which is won before it starts".to_string(),
+				);
+			}
+			_ => {}
+		}
+		if let Some(turn_limit) = self.defeat.turn_limit.as_ref().filter(|limit| *limit.get_ref() == 0) {
+			diagnostics.error(Some(turn_limit.start()), "a turn limit of 0 is lost before the first turn ends".to_string());
+		}
+		if let Some(lord) = &self.defeat.lord {
+			if !self
+				.units
+				.iter()
+				.any(|unit| unit.name == *lord && matches!(unit.faction, Faction::Player))
+			{
+				diagnostics.error(Some(lord.start()), format!("lord {:?} is not a player unit", lord.get_ref()));
+			}
+		}
+
+		diagnostics.into_result()
+	}
+}
@@ -0,0 +1,184 @@
+use crate::combat::{self, Combatant};
+use crate::game::LevelData;
+use crate::item::ItemKind;
+use crate::transform::Vector2D;
+use crate::unit::{Unit, UnitList};
+
+/// How a computer-controlled unit decides what to do on its turn.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Behavior {
+	/// Attacks the best target it can reach, or advances towards the nearest foe.
+	Aggressive,
+	/// Stays put until a foe wanders within reach, then behaves aggressively.
+	InRange,
+	/// Never leaves its tile, but attacks anyone in range of it. Used for bosses guarding a throne.
+	Guard,
+	/// Behaves aggressively until badly hurt, then flees to the safest healing tile it can reach
+	/// and drinks a healing item if it is carrying one.
+	Retreat,
+}
+
+/// What a unit has decided to do: move to `destination`, then attack `target` if there is one,
+/// or use the consumable in inventory slot `item`.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Decision {
+	pub destination: Vector2D<i16>,
+	pub target: Option<usize>,
+	pub item: Option<usize>,
+}
+
+/// Retreating units flee once their HP falls to this percentage of their maximum.
+pub const RETREAT_THRESHOLD: u16 = 50;
+
+/// Scores attacking `defender` from `position`, or returns None if the attack isn't possible.
+///
+/// Expected damage dealt counts double expected damage taken, so units favour trades
+/// where they come out ahead, and a chance of defeating the target outweighs nearly anything else.
+fn score_attack(level: &LevelData, attacker: &Unit, position: Vector2D<i16>, defender: &Unit) -> Option<i32> {
+	let distance = position.manhattan_distance(defender.position);
+	let weapon = attacker.equipped_weapon()?;
+	if distance > u8::MAX as u16 || !weapon.in_range(distance as u8) {
+		return None;
+	}
+
+	let forecast = combat::forecast(
+		&Combatant {
+			stats: &attacker.stats,
+			hp: attacker.hp,
+			weapon: Some(weapon),
+			terrain: level.terrain_at(position)?,
+		},
+		&Combatant {
+			stats: &defender.stats,
+			hp: defender.hp,
+			weapon: defender.equipped_weapon(),
+			terrain: level.terrain_at(defender.position)?,
+		},
+		distance as u8,
+	);
+
+	// Expected damage, in hundredths of a hit point.
+	let expected = |side: &combat::SideForecast| {
+		side.damage as i32 * side.attacks as i32 * side.hit as i32
+	};
+	let mut score = expected(&forecast.attacker) * 2 - expected(&forecast.defender);
+	if forecast.attacker.damage as u16 * forecast.attacker.attacks as u16 >= defender.hp as u16 {
+		score += forecast.attacker.hit as i32 * 100;
+	}
+	Some(score)
+}
+
+/// Finds the best attack available from any of `positions`.
+fn best_attack(
+	level: &LevelData,
+	units: &UnitList,
+	unit: usize,
+	positions: impl Iterator<Item = Vector2D<i16>>,
+) -> Option<(i32, Decision)> {
+	let attacker = units.get(unit)?;
+	let mut best: Option<(i32, Decision)> = None;
+	for position in positions {
+		for (target, defender) in units.iter() {
+			if !attacker.faction.is_hostile_to(defender.faction) {
+				continue;
+			}
+			let Some(score) = score_attack(level, attacker, position, defender) else {
+				continue;
+			};
+			if best.is_none_or(|(best, _)| score > best) {
+				best = Some((
+					score,
+					Decision {
+						destination: position,
+						target: Some(target),
+						item: None,
+					},
+				));
+			}
+		}
+	}
+	best
+}
+
+/// Returns the distance from a position to the nearest foe of `unit`, if it has any.
+fn nearest_foe(units: &UnitList, unit: &Unit, position: Vector2D<i16>) -> Option<u16> {
+	units
+		.iter()
+		.filter(|(_, other)| unit.faction.is_hostile_to(other.faction))
+		.map(|(_, other)| position.manhattan_distance(other.position))
+		.min()
+}
+
+/// Decides where a computer-controlled unit should move and who it should attack.
+pub fn decide(level: &LevelData, units: &UnitList, unit: usize) -> Option<Decision> {
+	let actor = units.get(unit)?;
+	let origin = actor.position;
+	let stay = Decision {
+		destination: origin,
+		target: None,
+		item: None,
+	};
+
+	let reachable = level.movement_range(actor, units);
+	let destinations = || {
+		reachable
+			.iter()
+			.filter(|position| units.at(*position).is_none_or(|other| other == unit))
+	};
+
+	match actor.behavior {
+		Behavior::Guard => Some(
+			best_attack(level, units, unit, [origin].into_iter())
+				.map_or(stay, |(_, decision)| decision),
+		),
+		Behavior::Retreat
+			if (actor.hp as u16) * 100 <= actor.stats.hp as u16 * RETREAT_THRESHOLD =>
+		{
+			// Prefer tiles that heal, then tiles far from danger, then tiles with good cover.
+			let destination = destinations()
+				.max_by_key(|position| {
+					let terrain = level.terrain_at(*position);
+					(
+						terrain.map_or(0, |terrain| terrain.heal),
+						nearest_foe(units, actor, *position).unwrap_or(u16::MAX),
+						terrain.map_or(0, |terrain| terrain.avoid as i16 + terrain.defense as i16),
+						*position == origin,
+					)
+				})
+				.unwrap_or(origin);
+			let item = actor
+				.inventory
+				.iter()
+				.find(|(_, item)| item.data().kind == ItemKind::Consumable && item.data().heal > 0)
+				.map(|(slot, _)| slot);
+			Some(Decision {
+				destination,
+				target: None,
+				item,
+			})
+		}
+		Behavior::InRange => Some(
+			best_attack(level, units, unit, destinations())
+				.map_or(stay, |(_, decision)| decision),
+		),
+		Behavior::Aggressive | Behavior::Retreat => {
+			if let Some((_, decision)) = best_attack(level, units, unit, destinations()) {
+				return Some(decision);
+			}
+			// Nobody is in reach, so close as much distance as possible.
+			let destination = destinations()
+				.min_by_key(|position| {
+					(
+						nearest_foe(units, actor, *position).unwrap_or(u16::MAX),
+						*position != origin,
+					)
+				})
+				.unwrap_or(origin);
+			Some(Decision {
+				destination,
+				target: None,
+				item: None,
+			})
+		}
+	}
+}
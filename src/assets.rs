@@ -0,0 +1,4 @@
+// One constant for every image in src/assets/gfx and map in src/assets/maps, generated by build.rs.
+// Referring to an asset which doesn't exist is a compile error.
+
+include!(concat!(env!("OUT_DIR"), "/assets/assets.rs"));
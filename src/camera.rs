@@ -0,0 +1,189 @@
+use crate::transform::Vector2D;
+
+pub const SCREEN_WIDTH: i16 = 240;
+pub const SCREEN_HEIGHT: i16 = 160;
+/// Size of a metatile, in pixels.
+pub const METATILE_SIZE: i16 = 16;
+/// A screenblock holds 32x32 tiles, or 16x16 metatiles. Positions wrap around it as the view scrolls.
+pub const SCREENBLOCK_METATILES: i16 = 16;
+
+/// How close (in metatiles) the cursor may get to the edge of the screen before the camera follows it.
+pub const MARGIN: i16 = 2;
+/// How many pixels the camera may move each frame.
+pub const SCROLL_SPEED: i16 = 4;
+
+/// A rectangle of metatiles, from `min` up to but not including `max`.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct TileRect {
+	pub min: Vector2D<i16>,
+	pub max: Vector2D<i16>,
+}
+
+impl TileRect {
+	pub fn contains(&self, position: Vector2D<i16>) -> bool {
+		position.x >= self.min.x
+			&& position.y >= self.min.y
+			&& position.x < self.max.x
+			&& position.y < self.max.y
+	}
+}
+
+/// Tracks which part of the map is on screen.
+pub struct Camera {
+	/// Top-left corner of the screen, in pixels.
+	pub position: Vector2D<i16>,
+	/// Where the camera is scrolling towards.
+	target: Vector2D<i16>,
+	/// Size of the map, in metatiles.
+	map_width: u16,
+	map_height: u16,
+}
+
+impl Camera {
+	pub fn new(map_width: u16, map_height: u16) -> Self {
+		Self {
+			position: Vector2D { x: 0, y: 0 },
+			target: Vector2D { x: 0, y: 0 },
+			map_width,
+			map_height,
+		}
+	}
+
+	/// Furthest the camera can scroll before showing past the edge of the map.
+	fn limit(&self) -> Vector2D<i16> {
+		Vector2D {
+			x: (self.map_width as i16 * METATILE_SIZE - SCREEN_WIDTH).max(0),
+			y: (self.map_height as i16 * METATILE_SIZE - SCREEN_HEIGHT).max(0),
+		}
+	}
+
+	/// Scrolls so that `tile` is at least MARGIN metatiles away from the edges of the screen,
+	/// unless that would show past the edge of the map.
+	pub fn follow(&mut self, tile: Vector2D<i16>) {
+		fn axis(target: i16, tile: i16, screen: i16, limit: i16) -> i16 {
+			let low = (tile - MARGIN) * METATILE_SIZE;
+			let high = (tile + 1 + MARGIN) * METATILE_SIZE - screen;
+			target.min(low).max(high).clamp(0, limit)
+		}
+
+		let limit = self.limit();
+		self.target = Vector2D {
+			x: axis(self.target.x, tile.x, SCREEN_WIDTH, limit.x),
+			y: axis(self.target.y, tile.y, SCREEN_HEIGHT, limit.y),
+		};
+		self.position.move_towards(self.target, SCROLL_SPEED);
+	}
+
+	/// Jumps straight to a position without scrolling, for use when a scene is entered.
+	pub fn snap(&mut self, tile: Vector2D<i16>) {
+		self.follow(tile);
+		self.position = self.target;
+	}
+
+	/// Returns the metatiles which are at least partially on screen.
+	pub fn visible_tiles(&self) -> TileRect {
+		TileRect {
+			min: Vector2D {
+				x: self.position.x.div_euclid(METATILE_SIZE),
+				y: self.position.y.div_euclid(METATILE_SIZE),
+			},
+			max: Vector2D {
+				x: (self.position.x + SCREEN_WIDTH - 1).div_euclid(METATILE_SIZE) + 1,
+				y: (self.position.y + SCREEN_HEIGHT - 1).div_euclid(METATILE_SIZE) + 1,
+			},
+		}
+	}
+
+	/// Converts a position on the map, in pixels, to a position on screen.
+	/// Returns None if an object of the given size at that position would be entirely off screen.
+	pub fn to_screen(&self, position: Vector2D<i16>, size: Vector2D<i16>) -> Option<Vector2D<i16>> {
+		let screen = Vector2D {
+			x: position.x - self.position.x,
+			y: position.y - self.position.y,
+		};
+		if screen.x + size.x <= 0
+			|| screen.y + size.y <= 0
+			|| screen.x >= SCREEN_WIDTH
+			|| screen.y >= SCREEN_HEIGHT
+		{
+			None
+		} else {
+			Some(screen)
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn at(x: i16, y: i16) -> Vector2D<i16> {
+		Vector2D { x, y }
+	}
+
+	const TILE: Vector2D<i16> = Vector2D {
+		x: METATILE_SIZE,
+		y: METATILE_SIZE,
+	};
+
+	#[test]
+	fn the_camera_keeps_a_margin_around_the_cursor() {
+		let mut camera = Camera::new(32, 32);
+		// The screen is 15 metatiles wide, so the camera stays put until the cursor is within MARGIN of its edge.
+		camera.snap(at(15 - 1 - MARGIN, 0));
+		assert_eq!(camera.position, at(0, 0));
+		camera.snap(at(15 - MARGIN, 0));
+		assert_eq!(camera.position, at(METATILE_SIZE, 0));
+
+		// Moving back doesn't scroll until the cursor reaches the margin on the other side.
+		camera.snap(at(1 + MARGIN, 0));
+		assert_eq!(camera.position, at(METATILE_SIZE, 0));
+		camera.snap(at(MARGIN, 0));
+		assert_eq!(camera.position, at(0, 0));
+	}
+
+	#[test]
+	fn following_scrolls_a_little_each_frame() {
+		let mut camera = Camera::new(32, 32);
+		let tile = at(15 - MARGIN, 10 - MARGIN);
+		camera.follow(tile);
+		assert_eq!(camera.position, at(SCROLL_SPEED, SCROLL_SPEED));
+		for _ in 0..METATILE_SIZE / SCROLL_SPEED {
+			camera.follow(tile);
+		}
+		assert_eq!(camera.position, at(METATILE_SIZE, METATILE_SIZE));
+	}
+
+	#[test]
+	fn the_camera_never_shows_past_the_edge_of_the_map() {
+		let mut camera = Camera::new(32, 32);
+		camera.snap(at(31, 31));
+		assert_eq!(camera.position, at(32 * METATILE_SIZE - SCREEN_WIDTH, 32 * METATILE_SIZE - SCREEN_HEIGHT));
+		camera.snap(at(0, 0));
+		assert_eq!(camera.position, at(0, 0));
+
+		// Maps smaller than the screen never scroll.
+		let mut small = Camera::new(10, 5);
+		small.snap(at(9, 4));
+		assert_eq!(small.position, at(0, 0));
+	}
+
+	#[test]
+	fn objects_off_screen_are_culled() {
+		let mut camera = Camera::new(32, 32);
+		camera.position = at(METATILE_SIZE, 0);
+
+		assert_eq!(camera.to_screen(at(METATILE_SIZE, 0), TILE), Some(at(0, 0)));
+		// Partly on screen.
+		assert_eq!(camera.to_screen(at(1, 0), TILE), Some(at(1 - METATILE_SIZE, 0)));
+		assert_eq!(
+			camera.to_screen(at(METATILE_SIZE + SCREEN_WIDTH - 1, 0), TILE),
+			Some(at(SCREEN_WIDTH - 1, 0))
+		);
+		// Entirely off screen.
+		assert_eq!(camera.to_screen(at(0, 0), TILE), None);
+		assert_eq!(camera.to_screen(at(METATILE_SIZE + SCREEN_WIDTH, 0), TILE), None);
+		assert_eq!(camera.to_screen(at(METATILE_SIZE, -METATILE_SIZE), TILE), None);
+		assert_eq!(camera.to_screen(at(METATILE_SIZE, SCREEN_HEIGHT), TILE), None);
+	}
+}
@@ -0,0 +1,105 @@
+use crate::console::{Input, Oam, Vram};
+use crate::game::{GameState, LevelData, TEXT_SCREENBLOCK};
+use crate::rng::Rng;
+use crate::scene::{Scene, SceneKind, Scenes, Transition};
+use crate::text::TextLayer;
+use crate::tools::include_resource;
+use crate::unit::Faction;
+use crate::video::Video;
+use core::fmt::Write;
+
+/// One chapter of the campaign, as listed in src/assets/campaign.toml.
+#[derive(Debug)]
+pub struct Chapter {
+	pub title: &'static str,
+	pub map: &'static LevelData<'static>,
+	/// A short description of the map's objective, shown before the chapter begins.
+	pub objective: &'static str,
+	/// The chapter which follows this one, or None if the campaign ends here.
+	pub next: Option<u8>,
+}
+
+/// Every chapter, in the order they are listed. A chapter's id is its index here, and the first is played first.
+pub const CHAPTERS: &[Chapter] = &include_resource!("campaign.rs");
+
+/// The name of every unit in the campaign.
+/// Saves store names as text, so they must be found here again when loading.
+pub const NAMES: &[&str] = &include_resource!("names.rs");
+
+/// The save slot the campaign is kept in, between chapters.
+pub const CAMPAIGN_SLOT: usize = 0;
+
+/// Returns true if a player unit called `name` was in any chapter played before `chapter`.
+/// Such a unit is only missing from the roster if it has fallen.
+pub fn appeared_before(chapter: u8, name: &str) -> bool {
+	let mut current = Some(0);
+	while let Some(id) = current.filter(|id| *id != chapter) {
+		let previous = &CHAPTERS[id as usize];
+		if previous
+			.map
+			.units
+			.iter()
+			.any(|unit| unit.faction == Faction::Player && unit.name == name)
+		{
+			return true;
+		}
+		current = previous.next;
+	}
+	false
+}
+
+/// Introduces a chapter with its title and objective, then begins it with the roster from the chapter before.
+pub struct ChapterIntro<V: Video> {
+	chapter: u8,
+	text: TextLayer<V>,
+	/// The chapter's RNG starts from this, before the player's inputs are mixed in.
+	pub rng: Rng,
+}
+
+impl<V: Video> ChapterIntro<V> {
+	pub fn new(video: V, chapter: u8, rng: Rng) -> Self {
+		Self {
+			chapter,
+			text: TextLayer::new(video, TEXT_SCREENBLOCK),
+			rng,
+		}
+	}
+
+	pub fn chapter(&self) -> u8 {
+		self.chapter
+	}
+}
+
+impl<'a, V: Video> Scene<'a, V> for ChapterIntro<V> {
+	fn enter(&mut self, vram: &mut Vram<V>) {
+		let chapter = &CHAPTERS[self.chapter as usize];
+		self.text.load(vram);
+		self.text.fill(1, 6, 28, 5);
+		write!(self.text.at(2, 7), "{}", chapter.title).ok();
+		write!(self.text.at(2, 9), "{}", chapter.objective).ok();
+	}
+
+	fn tick(&mut self, input: &Input) -> Transition<'a, V> {
+		if input.new.start() || input.new.a() {
+			Transition::Replace(begin_chapter)
+		} else {
+			Transition::None
+		}
+	}
+
+	fn draw(&mut self, _oam: &mut Oam) {}
+}
+
+/// Begins the introduced chapter with the roster from the chapter before.
+fn begin_chapter<V: Video>(scenes: &mut Scenes<V>) -> SceneKind {
+	let intro = scenes.intro.as_ref().expect("a chapter begins from its introduction");
+	let id = intro.chapter;
+	let mut game = GameState::with_roster(scenes.video, CHAPTERS[id as usize].map, &scenes.roster, |name| {
+		appeared_before(id, name)
+	})
+	.expect("failed to load level");
+	game.chapter = Some(id);
+	game.rng = intro.rng;
+	scenes.map = Some(game);
+	SceneKind::Map
+}
@@ -0,0 +1,39 @@
+use crate::combat::WeaponKind;
+use crate::stats::Stats;
+use crate::terrain::MovementClass;
+use crate::tools::Image;
+
+pub struct ClassData {
+	pub name: &'static str,
+	pub description: &'static str,
+	/// Stats of a level 1 unit of this class.
+	pub bases: Stats,
+	/// Chance, in percent, of each stat increasing on level up.
+	pub growths: Stats,
+	/// The highest each stat may grow.
+	pub caps: Stats,
+	pub movement_class: MovementClass,
+	/// Movement points available each turn.
+	pub movement: u8,
+	/// Weapon types this class can wield.
+	pub weapons: &'static [WeaponKind],
+	/// The sprite used to represent this class on the map.
+	pub sprite: Image,
+	/// Index into `class-icons.png`.
+	pub icon: u8,
+}
+
+// Compiled from `assets/classes.toml`: defines `ClassId` (with `ClassId::ALL`), `CLASS_COUNT` and `CLASSES`.
+include!(concat!(env!("OUT_DIR"), "/assets/classes.rs"));
+
+impl ClassData {
+	pub fn can_wield(&self, kind: WeaponKind) -> bool {
+		self.weapons.contains(&kind)
+	}
+}
+
+impl ClassId {
+	pub fn data(self) -> &'static ClassData {
+		&CLASSES[self as usize]
+	}
+}
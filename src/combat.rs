@@ -0,0 +1,246 @@
+use crate::stats::Stats;
+use crate::terrain::Terrain;
+
+/// Types of weapons, which determine who can wield them.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum WeaponKind {
+	Sword,
+	Lance,
+	Axe,
+	Bow,
+	Anima,
+	Light,
+	Dark,
+	Staff,
+}
+
+impl WeaponKind {
+	/// Returns 1 if this weapon has the advantage over `foe` in the weapon triangle,
+	/// -1 if it is at a disadvantage, and 0 otherwise.
+	/// Swords beat axes, axes beat lances, and lances beat swords.
+	pub fn triangle(self, foe: WeaponKind) -> i16 {
+		use WeaponKind::*;
+		match (self, foe) {
+			(Sword, Axe) | (Axe, Lance) | (Lance, Sword) => 1,
+			(Axe, Sword) | (Lance, Axe) | (Sword, Lance) => -1,
+			_ => 0,
+		}
+	}
+}
+
+/// The properties of a weapon which matter in combat.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Weapon {
+	pub kind: WeaponKind,
+	pub might: u8,
+	pub hit: u8,
+	pub crit: u8,
+	pub weight: u8,
+	pub min_range: u8,
+	pub max_range: u8,
+	/// Magical weapons use magic against resistance instead of strength against defense.
+	pub magical: bool,
+}
+
+impl Weapon {
+	pub fn in_range(&self, distance: u8) -> bool {
+		(self.min_range..=self.max_range).contains(&distance)
+	}
+}
+
+/// One side of a battle.
+pub struct Combatant<'a> {
+	pub stats: &'a Stats,
+	/// Current hit points.
+	pub hp: u8,
+	pub weapon: Option<Weapon>,
+	/// The terrain this unit is standing on.
+	pub terrain: &'a Terrain,
+}
+
+impl<'a> Combatant<'a> {
+	/// Speed after the weapon's weight is taken into account.
+	fn attack_speed(&self) -> i16 {
+		let weight = self.weapon.map_or(0, |weapon| weapon.weight) as i16;
+		self.stats.speed as i16 - (weight - self.stats.strength as i16).max(0)
+	}
+}
+
+/// What one side of a battle can expect to do to the other.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub struct SideForecast {
+	/// Hit points before combat begins.
+	pub hp: u8,
+	/// Damage dealt by each strike that lands, before critical hits.
+	pub damage: u8,
+	/// Chance of each strike landing, from 0 to 100.
+	pub hit: u8,
+	/// Chance of a landed strike being critical, from 0 to 100.
+	pub crit: u8,
+	/// Number of strikes: 0 if the unit cannot counter, 2 if it is fast enough to follow up.
+	pub attacks: u8,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Forecast {
+	pub attacker: SideForecast,
+	pub defender: SideForecast,
+}
+
+/// How much faster a unit must be than its opponent to strike twice.
+pub const FOLLOW_UP_SPEED: i16 = 4;
+
+/// Hit bonus for the side with the advantage in the weapon triangle, and penalty for the other.
+pub const TRIANGLE_HIT: i16 = 15;
+/// Damage bonus for the side with the advantage in the weapon triangle, and penalty for the other.
+pub const TRIANGLE_DAMAGE: i16 = 1;
+
+/// Predicts the outcome of `attacker` initiating combat with `defender` from `distance` tiles away.
+pub fn forecast(attacker: &Combatant, defender: &Combatant, distance: u8) -> Forecast {
+	fn side(unit: &Combatant, foe: &Combatant, distance: u8) -> SideForecast {
+		let Some(weapon) = unit.weapon.filter(|weapon| weapon.in_range(distance)) else {
+			return SideForecast {
+				hp: unit.hp,
+				..Default::default()
+			};
+		};
+
+		let (power, protection) = if weapon.magical {
+			(unit.stats.magic, foe.stats.resistance)
+		} else {
+			(unit.stats.strength, foe.stats.defense)
+		};
+		let triangle = foe.weapon.map_or(0, |foe| weapon.kind.triangle(foe.kind));
+		let attack = power as i16 + weapon.might as i16 + triangle * TRIANGLE_DAMAGE;
+		let protection = protection as i16 + foe.terrain.defense as i16;
+
+		let accuracy = weapon.hit as i16
+			+ unit.stats.skill as i16 * 2
+			+ unit.stats.luck as i16 / 2
+			+ triangle * TRIANGLE_HIT;
+		let avoid = foe.attack_speed() * 2 + foe.stats.luck as i16 + foe.terrain.avoid as i16;
+
+		let critical = weapon.crit as i16 + unit.stats.skill as i16 / 2;
+		let dodge = foe.stats.luck as i16;
+
+		SideForecast {
+			hp: unit.hp,
+			damage: (attack - protection).clamp(0, u8::MAX as i16) as u8,
+			hit: (accuracy - avoid).clamp(0, 100) as u8,
+			crit: (critical - dodge).clamp(0, 100) as u8,
+			attacks: if unit.attack_speed() - foe.attack_speed() >= FOLLOW_UP_SPEED { 2 } else { 1 },
+		}
+	}
+
+	Forecast {
+		attacker: side(attacker, defender, distance),
+		defender: side(defender, attacker, distance),
+	}
+}
+
+/// Decides whether strikes land. Implementors are expected to be deterministic for a given state,
+/// so that a battle can be replayed exactly.
+pub trait Rolls {
+	/// Returns true if a strike with a `chance`% hit rate lands.
+	fn hit(&mut self, chance: u8) -> bool;
+	/// Returns true if a landed strike with a `chance`% critical rate is critical.
+	fn crit(&mut self, chance: u8) -> bool;
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Side {
+	Attacker,
+	Defender,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Strike {
+	pub side: Side,
+	pub hit: bool,
+	pub crit: bool,
+	/// Damage dealt, including the critical bonus.
+	pub damage: u8,
+	/// True if this strike defeated its target.
+	pub lethal: bool,
+}
+
+/// The strikes exchanged during a battle, in order, along with each side's remaining hit points.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct CombatResult {
+	strikes: [Strike; 4],
+	length: usize,
+	pub attacker_hp: u8,
+	pub defender_hp: u8,
+}
+
+impl CombatResult {
+	pub fn strikes(&self) -> &[Strike] {
+		&self.strikes[..self.length]
+	}
+}
+
+/// Critical hits multiply damage by this amount.
+pub const CRIT_MULTIPLIER: u8 = 3;
+
+/// Plays out a forecast battle.
+/// The attacker strikes first, then the defender counters,
+/// then whichever side is fast enough follows up. Combat stops as soon as either side falls.
+pub fn resolve(forecast: &Forecast, rolls: &mut impl Rolls) -> CombatResult {
+	let mut result = CombatResult {
+		strikes: [Strike {
+			side: Side::Attacker,
+			hit: false,
+			crit: false,
+			damage: 0,
+			lethal: false,
+		}; 4],
+		length: 0,
+		attacker_hp: forecast.attacker.hp,
+		defender_hp: forecast.defender.hp,
+	};
+
+	let mut order = [Side::Attacker; 4];
+	let mut turns = 0;
+	for (side, attacks) in [
+		(Side::Attacker, forecast.attacker.attacks.min(1)),
+		(Side::Defender, forecast.defender.attacks.min(1)),
+		(Side::Attacker, forecast.attacker.attacks.saturating_sub(1)),
+		(Side::Defender, forecast.defender.attacks.saturating_sub(1)),
+	] {
+		for _ in 0..attacks {
+			order[turns] = side;
+			turns += 1;
+		}
+	}
+
+	for side in &order[..turns] {
+		let (stats, target_hp) = match side {
+			Side::Attacker => (&forecast.attacker, &mut result.defender_hp),
+			Side::Defender => (&forecast.defender, &mut result.attacker_hp),
+		};
+
+		let hit = rolls.hit(stats.hit);
+		let crit = hit && rolls.crit(stats.crit);
+		let damage = match (hit, crit) {
+			(false, _) => 0,
+			(true, false) => stats.damage,
+			(true, true) => stats.damage.saturating_mul(CRIT_MULTIPLIER),
+		};
+		*target_hp = target_hp.saturating_sub(damage);
+
+		result.strikes[result.length] = Strike {
+			side: *side,
+			hit,
+			crit,
+			damage,
+			lethal: *target_hp == 0,
+		};
+		result.length += 1;
+
+		if *target_hp == 0 {
+			break;
+		}
+	}
+
+	result
+}
@@ -1,80 +1,28 @@
 #![allow(dead_code)]
-#![allow(non_upper_case_globals)]
-#![allow(unused_macros)]
-#![allow(unused_imports)]
-
-pub use gba::bios::VBlankIntrWait as wait_vblank;
 
 use crate::transform::AxisX;
 use crate::transform::AxisY;
 use crate::transform::Direction4;
+use crate::video::{Sprite, Video};
 use core::cmp::max;
-use core::fmt::Write;
-use gba::bios::IntrWait as wait_intr;
-use gba::interrupts::IrqBits;
-use gba::keys::KeyInput;
-use gba::mmio;
-use gba::video::obj::{ObjAttr, ObjAttr0, ObjDisplayStyle};
-use gba::video::Color;
-use voladdress::{Safe, VolBlock};
-
-// Sprite sizes.
-pub const S8x8: u16 = 0;
-pub const S16x16: u16 = 1;
-pub const S32x32: u16 = 2;
-pub const S64x64: u16 = 3;
-pub const H16x8: u16 = 0;
-pub const H32x8: u16 = 1;
-pub const H32x16: u16 = 2;
-pub const H64x32: u16 = 3;
-pub const V8x16: u16 = 0;
-pub const V8x32: u16 = 1;
-pub const V16x32: u16 = 2;
-pub const V32x64: u16 = 3;
-
-pub const VRAM_BLOCK0: VolBlock<u32, Safe, Safe, 0x1000> = unsafe { VolBlock::new(0x06000000) };
-pub const VRAM_OBJS: VolBlock<u32, Safe, Safe, 0x1000> = unsafe { VolBlock::new(0x06010000) };
-
-/// Formats and prints a message to the emulator.
-/// The message is marked as "Info".
-macro_rules! println {
-	($($args:expr),+) => {
-		let log_level = gba::mgba::MgbaMessageLevel::Info;
-		if let Ok(mut logger) = gba::prelude::MgbaBufferedLogger::try_new(log_level) {
-			writeln!(logger, $($args),+).ok();
-		}
-	}
-}
-
-pub(crate) use println;
-
-/// Formats and prints a message to the emulator.
-/// The message is marked as "Error".
-macro_rules! eprintln {
-	($($args:expr),+) => {
-		let log_level = gba::mgba::MgbaMessageLevel::Error;
-		if let Ok(mut logger) = gba::prelude::MgbaBufferedLogger::try_new(log_level) {
-			writeln!(logger, $($args),+).ok();
-		}
-	}
-}
-
-pub(crate) use eprintln;
+use core::ops::{BitAnd, BitOr, Not};
 
 // VRAM allocation is fun because unlike OAM you can't just reset it every frame.
 // A smarter allocator would probably allow you to free memory,
 // but I'd much rather just clear it between game states.
 // If "scratch" tiles are needed then they should just be allocated ahead of time.
 // But then you may as well ditch the runtime code altogether and generate a bunch of constants.
-pub struct Vram {
+pub struct Vram<V: Video> {
+	pub video: V,
 	pub index: usize,
 	pub bg_palette: usize,
 	pub obj_palette: usize,
 }
 
-impl Vram {
-	pub fn new() -> Self {
+impl<V: Video> Vram<V> {
+	pub const fn new(video: V) -> Self {
 		Self {
+			video,
 			index: 0,
 			bg_palette: 0,
 			obj_palette: 0,
@@ -83,44 +31,34 @@ impl Vram {
 
 	pub fn reset(&mut self) {
 		self.index = 0;
+		self.bg_palette = 0;
+		self.obj_palette = 0;
 	}
 
 	pub fn load_4bpp_bg_texture(&mut self, data: &[u32]) -> u16 {
 		let id = self.index;
-		for (i, word) in data.iter().enumerate() {
-			VRAM_BLOCK0.index(self.index * 8 + i).write(*word);
-		}
+		self.video.load_bg_tiles(self.index, data);
 		self.index += data.len().div_ceil(8);
 		id as u16
 	}
 
 	pub fn load_4bpp_obj_texture(&mut self, data: &[u32]) -> u16 {
 		let id = self.index;
-		for (i, word) in data.iter().enumerate() {
-			VRAM_OBJS.index(self.index * 8 + i).write(*word);
-		}
+		self.video.load_obj_tiles(self.index, data);
 		self.index += data.len().div_ceil(8);
 		id as u16
 	}
 
 	pub fn load_bg_palette(&mut self, data: &[u16]) -> u16 {
 		let id = self.bg_palette;
-		for (i, word) in data.iter().enumerate() {
-			mmio::BG_PALETTE
-				.index(1 + self.bg_palette * 16 + i)
-				.write(Color(*word));
-		}
+		self.video.load_bg_colors(1 + self.bg_palette * 16, data);
 		self.bg_palette += data.len().div_ceil(16);
 		id as u16
 	}
 
 	pub fn load_obj_palette(&mut self, data: &[u16]) -> u16 {
 		let id = self.obj_palette;
-		for (i, word) in data.iter().enumerate() {
-			mmio::OBJ_PALETTE
-				.index(1 + self.obj_palette * 16 + i)
-				.write(Color(*word));
-		}
+		self.video.load_obj_colors(1 + self.obj_palette * 16, data);
 		self.obj_palette += data.len().div_ceil(16);
 		id as u16
 	}
@@ -130,7 +68,13 @@ impl Vram {
 pub struct Oam {
 	index: usize,
 	last_index: usize,
-	entries: [ObjAttr; 128],
+	entries: [Sprite; 128],
+}
+
+impl Default for Oam {
+	fn default() -> Self {
+		Self::new()
+	}
 }
 
 impl Oam {
@@ -138,64 +82,212 @@ impl Oam {
 		Oam {
 			index: 128,
 			last_index: 0,
-			entries: [(ObjAttr::new()); 128],
+			entries: [Sprite::default(); 128],
 		}
 	}
 
 	/// Clears all dirty oam entries.
 	pub fn clean(&mut self) {
-		for i in 0..self.index {
-			self.entries[i].0 = ObjAttr0::new().with_style(ObjDisplayStyle::NotDisplayed);
-		}
 		self.last_index = self.index;
 		self.index = 0;
 	}
 
 	/// Pushes all entries to OAM, allowing the PPU to display them.
-	pub fn commit(&self) {
+	/// Entries which were used last frame but not this one are hidden.
+	pub fn commit(&self, video: &impl Video) {
 		for i in 0..max(self.index, self.last_index) {
-			mmio::OBJ_ATTR0.index(i).write(self.entries[i].0);
-			mmio::OBJ_ATTR1.index(i).write(self.entries[i].1);
-			mmio::OBJ_ATTR2.index(i).write(self.entries[i].2);
+			video.set_sprite(i, self.sprites().get(i).copied());
 		}
 	}
 
 	/// Returns an OAM entry for the calling code to use as needed.
-	pub fn reserve_entry(&mut self) -> &mut ObjAttr {
+	pub fn reserve_entry(&mut self) -> &mut Sprite {
 		let result = &mut self.entries[self.index];
+		*result = Sprite::default();
 		self.index += 1;
 		result
 	}
+
+	/// Returns the entries reserved this frame, in priority order.
+	pub fn sprites(&self) -> &[Sprite] {
+		&self.entries[..self.index.min(self.entries.len())]
+	}
 }
 
+/// A set of GBA keys, in the KEYINPUT bit layout with pressed keys set to 1.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub struct Keys(pub u16);
+
+impl Keys {
+	pub const NONE: Keys = Keys(0);
+	pub const A: Keys = Keys(1 << 0);
+	pub const B: Keys = Keys(1 << 1);
+	pub const SELECT: Keys = Keys(1 << 2);
+	pub const START: Keys = Keys(1 << 3);
+	pub const RIGHT: Keys = Keys(1 << 4);
+	pub const LEFT: Keys = Keys(1 << 5);
+	pub const UP: Keys = Keys(1 << 6);
+	pub const DOWN: Keys = Keys(1 << 7);
+	pub const R: Keys = Keys(1 << 8);
+	pub const L: Keys = Keys(1 << 9);
+
+	pub const fn contains(self, keys: Keys) -> bool {
+		self.0 & keys.0 == keys.0
+	}
+
+	pub const fn is_empty(self) -> bool {
+		self.0 == 0
+	}
+
+	pub const fn a(self) -> bool {
+		self.contains(Keys::A)
+	}
+
+	pub const fn b(self) -> bool {
+		self.contains(Keys::B)
+	}
+
+	pub const fn select(self) -> bool {
+		self.contains(Keys::SELECT)
+	}
+
+	pub const fn start(self) -> bool {
+		self.contains(Keys::START)
+	}
+
+	pub const fn right(self) -> bool {
+		self.contains(Keys::RIGHT)
+	}
+
+	pub const fn left(self) -> bool {
+		self.contains(Keys::LEFT)
+	}
+
+	pub const fn up(self) -> bool {
+		self.contains(Keys::UP)
+	}
+
+	pub const fn down(self) -> bool {
+		self.contains(Keys::DOWN)
+	}
+
+	pub const fn r(self) -> bool {
+		self.contains(Keys::R)
+	}
+
+	pub const fn l(self) -> bool {
+		self.contains(Keys::L)
+	}
+}
+
+impl BitAnd for Keys {
+	type Output = Keys;
+
+	fn bitand(self, rhs: Keys) -> Keys {
+		Keys(self.0 & rhs.0)
+	}
+}
+
+impl BitOr for Keys {
+	type Output = Keys;
+
+	fn bitor(self, rhs: Keys) -> Keys {
+		Keys(self.0 | rhs.0)
+	}
+}
+
+impl Not for Keys {
+	type Output = Keys;
+
+	fn not(self) -> Keys {
+		Keys(!self.0 & 0x3FF)
+	}
+}
+
+/// Somewhere to read the keypad from: the hardware, or a script.
+pub trait KeySource {
+	/// Returns the keys held this frame. Called once per frame.
+	fn read(&mut self) -> Keys;
+}
+
+/// Frames a key must be held before it starts repeating.
+pub const DEFAULT_REPEAT_DELAY: u8 = 16;
+/// Frames between repeats of a held key.
+pub const DEFAULT_REPEAT_RATE: u8 = 4;
+
 /// Contains the current frame's input state.
 /// Must be updated once (and only once) each frame with the .update() function.
 pub struct Input {
-	pub held: KeyInput,
-	pub new: KeyInput,
-	pub released: KeyInput,
-	last: KeyInput,
+	pub held: Keys,
+	pub new: Keys,
+	pub released: Keys,
+	/// Keys which were just pressed, or have been held long enough to repeat.
+	/// Useful for scrolling through menus and across the map.
+	pub repeated: Keys,
+	/// Frames a key must be held before it begins to repeat.
+	pub repeat_delay: u8,
+	/// Frames between each repeat once a key has begun repeating.
+	pub repeat_rate: u8,
+	repeat_timer: u8,
+	last: Keys,
+	/// Number of times .update() has been called.
+	pub frame: u32,
+}
+
+impl Default for Input {
+	fn default() -> Self {
+		Self::new()
+	}
 }
 
 impl Input {
 	pub fn new() -> Self {
 		Self {
-			held: KeyInput::new(),
-			last: KeyInput::new(),
-			new: KeyInput::new(),
-			released: KeyInput::new(),
+			held: Keys::NONE,
+			last: Keys::NONE,
+			new: Keys::NONE,
+			released: Keys::NONE,
+			repeated: Keys::NONE,
+			repeat_delay: DEFAULT_REPEAT_DELAY,
+			repeat_rate: DEFAULT_REPEAT_RATE,
+			repeat_timer: 0,
+			frame: 0,
 		}
 	}
 
 	/// Re-read the keys for this frame.
-	pub fn update(&mut self) {
+	pub fn update(&mut self, source: &mut impl KeySource) {
 		self.last = self.held;
-		self.held = mmio::KEYINPUT.read();
+		self.held = source.read();
 		self.new = self.held & !self.last;
 		self.released = !self.held & self.last;
+		self.frame = self.frame.wrapping_add(1);
+
+		// Pressing another key restarts the delay, so changing direction never skips a step.
+		self.repeated = self.new;
+		if !self.new.is_empty() {
+			self.repeat_timer = self.repeat_delay;
+		} else if !self.held.is_empty() {
+			self.repeat_timer = self.repeat_timer.saturating_sub(1);
+			if self.repeat_timer == 0 {
+				self.repeated = self.held;
+				self.repeat_timer = self.repeat_rate.max(1);
+			}
+		}
 	}
 
-	fn get_direction4(input: KeyInput) -> Option<Direction4> {
+	/// Returns true if any key was pressed this frame.
+	pub fn any_new(&self) -> bool {
+		!self.new.is_empty()
+	}
+
+	/// Combines the frame count with the keys pressed this frame.
+	/// Players can't time their inputs to the frame, so this makes a good source of randomness.
+	pub fn entropy(&self) -> u32 {
+		self.frame ^ (self.new.0 as u32) << 16
+	}
+
+	fn get_direction4(input: Keys) -> Option<Direction4> {
 		if input.up() {
 			Some(Direction4::Up)
 		} else if input.right() {
@@ -221,7 +313,11 @@ impl Input {
 		Self::get_direction4(self.released)
 	}
 
-	fn get_x(input: KeyInput) -> Option<AxisX> {
+	pub fn get_repeated_direction4(&self) -> Option<Direction4> {
+		Self::get_direction4(self.repeated)
+	}
+
+	fn get_x(input: Keys) -> Option<AxisX> {
 		if input.left() {
 			Some(AxisX::Left)
 		} else if input.right() {
@@ -243,7 +339,11 @@ impl Input {
 		Self::get_x(self.released)
 	}
 
-	fn get_y(input: KeyInput) -> Option<AxisY> {
+	pub fn get_repeated_x(&self) -> Option<AxisX> {
+		Self::get_x(self.repeated)
+	}
+
+	fn get_y(input: Keys) -> Option<AxisY> {
 		if input.up() {
 			Some(AxisY::Up)
 		} else if input.down() {
@@ -264,9 +364,72 @@ impl Input {
 	pub fn get_released_y(&self) -> Option<AxisY> {
 		Self::get_y(self.released)
 	}
+
+	pub fn get_repeated_y(&self) -> Option<AxisY> {
+		Self::get_y(self.repeated)
+	}
 }
 
-/// Shorthand for [`IntrWait(true, IrqBits::HBLANK)`](wait_intr)
-pub fn wait_hblank() {
-	wait_intr(true, IrqBits::new().with_hblank(true));
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	/// Plays back one set of keys per frame.
+	struct Script<'k>(&'k [Keys]);
+
+	impl KeySource for Script<'_> {
+		fn read(&mut self) -> Keys {
+			let (keys, rest) = self.0.split_first().unwrap();
+			self.0 = rest;
+			*keys
+		}
+	}
+
+	/// Feeds `keys` to `input` one frame at a time, and returns the frames on which `key` repeated.
+	fn repeats(input: &mut Input, keys: &[Keys], key: Keys) -> Vec<usize> {
+		let mut script = Script(keys);
+		(0..keys.len())
+			.filter(|_| {
+				input.update(&mut script);
+				input.repeated.contains(key)
+			})
+			.collect()
+	}
+
+	#[test]
+	fn held_keys_repeat_after_a_delay() {
+		let mut input = Input::new();
+		input.repeat_delay = 3;
+		input.repeat_rate = 2;
+		let frames = repeats(&mut input, &[Keys::RIGHT; 10], Keys::RIGHT);
+		assert_eq!(frames, [0, 3, 5, 7, 9]);
+
+		// Letting go and pressing again starts from a fresh delay.
+		let mut keys = vec![Keys::NONE];
+		keys.extend([Keys::RIGHT; 4]);
+		assert_eq!(repeats(&mut input, &keys, Keys::RIGHT), [1, 4]);
+	}
+
+	#[test]
+	fn a_new_key_restarts_the_delay() {
+		let mut input = Input::new();
+		input.repeat_delay = 3;
+		input.repeat_rate = 1;
+		let mut keys = vec![Keys::RIGHT; 3];
+		keys.extend([Keys::RIGHT | Keys::DOWN; 4]);
+		assert_eq!(repeats(&mut input, &keys, Keys::RIGHT), [0, 6]);
+		// Only the new key counts as newly pressed.
+		assert_eq!(input.new, Keys::NONE);
+		assert_eq!(input.held, Keys::RIGHT | Keys::DOWN);
+	}
+
+	#[test]
+	fn released_keys_stop_repeating() {
+		let mut input = Input::new();
+		input.repeat_delay = 1;
+		input.repeat_rate = 1;
+		let keys = [Keys::A, Keys::A, Keys::NONE, Keys::NONE];
+		assert_eq!(repeats(&mut input, &keys, Keys::A), [0, 1]);
+		assert_eq!(input.repeated, Keys::NONE);
+	}
 }
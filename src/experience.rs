@@ -0,0 +1,164 @@
+use crate::rng::Rng;
+use crate::stats::Stats;
+
+/// Experience needed to gain a level.
+pub const EXP_PER_LEVEL: u8 = 100;
+pub const MAX_LEVEL: u8 = 20;
+
+/// Fixed growths start halfway to a stat increase, so that they round to the nearest point.
+pub const INITIAL_GROWTH_PROGRESS: Stats = Stats::splat(50);
+
+/// How a battle ended for the unit receiving experience.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum CombatOutcome {
+	/// None of the unit's strikes landed.
+	Missed,
+	/// At least one strike landed, but the foe survived.
+	Damaged,
+	Defeated,
+}
+
+/// Experience for fighting a foe of `foe_level`.
+/// Fighting stronger foes is worth more, and defeating a boss is worth a bonus.
+pub fn combat_exp(level: u8, foe_level: u8, foe_is_boss: bool, outcome: CombatOutcome) -> u8 {
+	let difference = foe_level as i16 - level as i16;
+	let exp = match outcome {
+		CombatOutcome::Missed => 1,
+		CombatOutcome::Damaged => (31 + difference) / 3,
+		CombatOutcome::Defeated => {
+			(31 + difference) / 3 + 20 + difference * 3 + if foe_is_boss { 40 } else { 0 }
+		}
+	};
+	exp.clamp(1, EXP_PER_LEVEL as i16) as u8
+}
+
+/// Experience for healing a unit of `target_level`.
+pub fn heal_exp(level: u8, target_level: u8) -> u8 {
+	let difference = target_level as i16 - level as i16;
+	(11 + difference).clamp(1, EXP_PER_LEVEL as i16) as u8
+}
+
+/// The result of a level up, for the UI to display.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct LevelUp {
+	/// The new level.
+	pub level: u8,
+	/// Stats before the level up.
+	pub before: Stats,
+	/// How much each stat increased.
+	pub gains: Stats,
+}
+
+impl LevelUp {
+	pub fn after(&self) -> Stats {
+		let before = self.before.to_array();
+		let gains = self.gains.to_array();
+		Stats::from_array(core::array::from_fn(|i| before[i] + gains[i]))
+	}
+}
+
+/// Rolls each stat against its growth rate, without exceeding `caps`.
+/// Returns how much each stat increased.
+pub fn grow(stats: &mut Stats, progress: &mut Stats, growths: &Stats, caps: &Stats, rng: &mut Rng) -> Stats {
+	let mut current = stats.to_array();
+	let mut current_progress = progress.to_array();
+	let growths = growths.to_array();
+	let caps = caps.to_array();
+	let mut gains = [0; Stats::COUNT];
+
+	for i in 0..Stats::COUNT {
+		let gain = rng.roll_growth(growths[i], &mut current_progress[i]);
+		gains[i] = gain.min(caps[i].saturating_sub(current[i]));
+		current[i] += gains[i];
+	}
+
+	*stats = Stats::from_array(current);
+	*progress = Stats::from_array(current_progress);
+	Stats::from_array(gains)
+}
+
+/// Stats of a unit which has gained `levels` levels with exactly average growths.
+/// Used for units which are placed at a level above 1.
+pub fn average_stats(bases: &Stats, growths: &Stats, caps: &Stats, levels: u8) -> Stats {
+	let bases = bases.to_array();
+	let growths = growths.to_array();
+	let caps = caps.to_array();
+	Stats::from_array(core::array::from_fn(|i| {
+		let gained = (growths[i] as u16 * levels as u16 + 50) / 100;
+		(bases[i] as u16 + gained).min(caps[i] as u16) as u8
+	}))
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::ai::Behavior;
+	use crate::class::ClassId;
+	use crate::game::UnitData;
+	use crate::unit::{Faction, Unit};
+
+	fn fighter(level: u8) -> Unit<'static> {
+		Unit::new(&UnitData {
+			name: "Fighter",
+			class: ClassId::Fighter,
+			x: 0,
+			y: 0,
+			is_boss: false,
+			level,
+			faction: Faction::Player,
+			items: &[],
+			behavior: Behavior::Aggressive,
+		})
+	}
+
+	#[test]
+	fn combat_exp_is_capped_at_one_level() {
+		assert_eq!(combat_exp(1, 1, false, CombatOutcome::Missed), 1);
+		assert_eq!(combat_exp(1, 20, true, CombatOutcome::Defeated), EXP_PER_LEVEL);
+		// Even a foe far below the unit is worth something.
+		assert_eq!(combat_exp(20, 1, false, CombatOutcome::Defeated), 1);
+		assert_eq!(heal_exp(20, 1), 1);
+	}
+
+	#[test]
+	fn reaching_the_threshold_levels_up_and_keeps_the_rest() {
+		let mut unit = fighter(1);
+		let mut rng = Rng::new(1);
+		assert_eq!(unit.gain_exp(90, &mut rng), None);
+		assert_eq!(unit.exp, 90);
+
+		let hp = unit.hp;
+		let level_up = unit.gain_exp(30, &mut rng).unwrap();
+		assert_eq!(level_up.level, 2);
+		assert_eq!(unit.level, 2);
+		assert_eq!(unit.exp, 20);
+		assert_eq!(level_up.after(), unit.stats);
+		assert_eq!(unit.hp, hp + level_up.gains.hp);
+	}
+
+	#[test]
+	fn exp_stops_at_the_max_level() {
+		let mut unit = fighter(MAX_LEVEL - 1);
+		let mut rng = Rng::new(1);
+		unit.exp = 99;
+		assert_eq!(unit.gain_exp(EXP_PER_LEVEL, &mut rng).unwrap().level, MAX_LEVEL);
+		assert_eq!(unit.exp, 0);
+
+		assert_eq!(unit.gain_exp(EXP_PER_LEVEL, &mut rng), None);
+		assert_eq!((unit.level, unit.exp), (MAX_LEVEL, 0));
+	}
+
+	#[test]
+	fn growth_stops_at_the_caps() {
+		let caps = Stats::splat(10);
+		let mut stats = Stats { hp: 9, ..caps };
+		let mut progress = Stats::splat(0);
+		let gains = grow(&mut stats, &mut progress, &Stats::splat(200), &caps, &mut Rng::new(1));
+		assert_eq!(gains, Stats { hp: 1, ..Stats::splat(0) });
+		assert_eq!(stats, caps);
+
+		assert_eq!(average_stats(&Stats::splat(5), &Stats::splat(100), &caps, 19), caps);
+		// Half of a 50% growth rounds up.
+		assert_eq!(average_stats(&Stats::splat(5), &Stats::splat(50), &caps, 1), Stats::splat(6));
+	}
+}
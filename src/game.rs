@@ -1,10 +1,26 @@
+use crate::ai::{self, Behavior, Decision};
+use crate::assets;
+use crate::camera::{Camera, TileRect, SCREENBLOCK_METATILES};
+use crate::campaign::{self, ChapterIntro, CAMPAIGN_SLOT};
+use crate::class::{ClassId, CLASS_COUNT};
+use crate::combat::{self, Combatant, Forecast, Side};
 use crate::console::*;
-use crate::tools::include_aligned_resource;
-use crate::transform::{AxisX, AxisY, Vector2D};
-use gba::mmio::TextScreenblockAddress;
-use gba::video::TextEntry;
-use gba::video::obj::{ObjAttr, ObjAttr0, ObjAttr1, ObjAttr2};
-use gba::Align4;
+use crate::item::ItemId;
+use crate::menu::{Menu, MenuEvent};
+use crate::experience::{self, CombatOutcome, LevelUp};
+use crate::movement::{attack_range, flood_fill, MapBitmap};
+use crate::objective::{ChapterResult, Defeat, Objective};
+use crate::terrain::{Terrain, TERRAIN};
+use crate::rng::Rng;
+use crate::save::{self, CampaignSave, Reader, SaveError, Writer, SUSPEND_SLOT};
+use crate::scene::{Scene, SceneKind, Scenes, Transition};
+use crate::text::TextLayer;
+use crate::title;
+use crate::transform::{AxisX, AxisY, Bounds, Direction4, Edge, Vector2D};
+use crate::turn::TurnState;
+use crate::unit::{Faction, RosterFull, Unit, UnitList, UnitSprite, MAX_UNITS};
+use crate::video::{Sprite, SpriteSize, TileEntry, Video};
+use core::fmt::Write;
 
 struct Cursor {
 	position: Vector2D<i16>,
@@ -21,19 +37,23 @@ enum CursorState {
 }
 
 impl Cursor {
-	fn new(vram: &mut Vram) -> Self {
+	fn new() -> Self {
 		Self {
 			position: Vector2D { x: 0, y: 0 },
 			sprite_position: Vector2D { x: 0, y: 0 },
-			tile_id: vram.load_4bpp_obj_texture(
-				&include_aligned_resource!("gfx/cursor.4bpp").as_u32_slice(),
-			),
-			palette: vram.load_obj_palette(&include_aligned_resource!("gfx/cursor.pal").as_u16_slice()),
+			tile_id: 0,
+			palette: 0,
 			bounce_timer: 0,
 		}
 	}
 
-	fn draw(&mut self, oam: &mut Oam, state: CursorState) {
+	fn load<V: Video>(&mut self, vram: &mut Vram<V>) {
+		let cursor = assets::gfx::CURSOR;
+		self.tile_id = vram.load_4bpp_obj_texture(cursor.tiles());
+		self.palette = vram.load_obj_palette(cursor.palette());
+	}
+
+	fn draw(&mut self, oam: &mut Oam, state: CursorState, camera: &Camera) {
 		fn bounce_offset(timer: &mut u8) -> i16 {
 			*timer += 1;
 			match timer {
@@ -53,18 +73,19 @@ impl Cursor {
 		self.sprite_position.move_towards(self.position * 16, 4);
 
 		let make_corner = |x_off, y_off, hflip, vflip| {
-			let mut cursor_base = ObjAttr::new();
-			cursor_base.0 = ObjAttr0::new()
-				.with_y((self.sprite_position.y + y_off) as u16);
-			cursor_base.1 = ObjAttr1::new()
-				.with_x((self.sprite_position.x + x_off) as u16)
-				.with_hflip(hflip)
-				.with_vflip(vflip)
-				.with_size(S8x8);
-			cursor_base.2 = ObjAttr2::new()
-				.with_tile_id(self.tile_id)
-				.with_palbank(self.palette);
-			cursor_base
+			let position = camera.to_screen(
+				self.sprite_position + Vector2D { x: x_off, y: y_off },
+				Vector2D { x: 8, y: 8 },
+			)?;
+			Some(Sprite {
+				x: position.x,
+				y: position.y,
+				tile_id: self.tile_id,
+				palbank: self.palette,
+				size: SpriteSize::S8x8,
+				hflip,
+				vflip,
+			})
 		};
 
 		let offset = match state {
@@ -79,26 +100,35 @@ impl Cursor {
 			}
 		};
 
-		let sprite = oam.reserve_entry();
-		*sprite = make_corner(-offset, -offset, false, false);
-		let sprite = oam.reserve_entry();
-		*sprite = make_corner(8 + offset, -offset, true, false);
-		let sprite = oam.reserve_entry();
-		*sprite = make_corner(-offset, 8 + offset, false, true);
-		let sprite = oam.reserve_entry();
-		*sprite = make_corner(8 + offset, 8 + offset, true, true);
+		for corner in [
+			make_corner(-offset, -offset, false, false),
+			make_corner(8 + offset, -offset, true, false),
+			make_corner(-offset, 8 + offset, false, true),
+			make_corner(8 + offset, 8 + offset, true, true),
+		]
+		.into_iter()
+		.flatten()
+		{
+			*oam.reserve_entry() = corner;
+		}
 	}
 }
  
 #[derive(Debug)]
 pub struct UnitData<'a> {
 	pub name: &'a str,
+	pub class: ClassId,
 	pub x: u16,
 	pub y: u16,
 	/// Determines whether or not a unit is marked as a boss.
 	/// Object Property: boss (boolean).
 	pub is_boss: bool,
 	pub level: u8,
+	pub faction: Faction,
+	/// Items the unit starts with. The first usable weapon is equipped.
+	pub items: &'a [ItemId],
+	/// How the unit acts when it isn't controlled by the player.
+	pub behavior: Behavior,
 }
 
 #[derive(Debug)]
@@ -106,154 +136,1404 @@ pub struct LevelData<'a> {
 	pub width: u16,
 	pub height: u16,
 	pub map: &'a [u8],
-	pub units: &'a [UnitData<'a>]
+	pub units: &'a [UnitData<'a>],
+	/// Tiles where units from the roster are placed if the map doesn't give them a position.
+	pub spawns: &'a [Vector2D<i16>],
+	pub objective: Objective,
+	pub defeat: Defeat<'a>,
 }
 
-struct Unit {
-	position: Vector2D<i16>,
-	tile_id: u16,
-	palette: u16,
-	animation_timer: u8,
+impl<'a> LevelData<'a> {
+	pub fn bounds(&self) -> Bounds {
+		Bounds::new(self.width, self.height)
+	}
+
+	/// Returns the tileset index at a position, or None if the position is outside of the map.
+	pub fn tile_at(&self, position: Vector2D<i16>) -> Option<u8> {
+		if self.bounds().contains(position) {
+			Some(self.map[position.x as usize + position.y as usize * self.width as usize])
+		} else {
+			None
+		}
+	}
+
+	/// Returns the terrain at a position, or None if the position is outside of the map.
+	pub fn terrain_at(&self, position: Vector2D<i16>) -> Option<&'static Terrain> {
+		TERRAIN.from_tile(self.tile_at(position)?)
+	}
+
+	/// Flood-fills the map from a unit's position using its movement stat.
+	/// Allies can be passed through, but not stopped on. Tiles held by hostile units block movement.
+	pub fn movement_range(&self, unit: &Unit, units: &UnitList) -> MapBitmap {
+		flood_fill(
+			unit.position,
+			unit.movement,
+			self.width,
+			self.height,
+			|position| {
+				let blocked = units
+					.at(position)
+					.and_then(|other| units.get(other))
+					.is_some_and(|other| unit.faction.is_hostile_to(other.faction));
+				if blocked {
+					return None;
+				}
+				self.terrain_at(position)
+					.and_then(|terrain| terrain.movement_cost(unit.movement_class))
+			},
+		)
+	}
 }
 
-impl Unit {
-	fn new(vram: &mut Vram) -> Self {
-		Self {
-			position: Vector2D { x: 0, y: 0 },
-			tile_id: vram.load_4bpp_obj_texture(
-				&include_aligned_resource!("gfx/luvui.4bpp").as_u32_slice(),
-			),
-			palette: vram.load_obj_palette(&include_aligned_resource!("gfx/luvui.pal").as_u16_slice()),
-			animation_timer: 0,
+/// BG0: the level's tilemap.
+pub const MAP_SCREENBLOCK: usize = 8;
+/// BG1: movement and attack range highlights, blended over the map.
+pub const OVERLAY_SCREENBLOCK: usize = 9;
+
+/// Writes the four 8x8 tiles of a 16x16 metatile to a screenblock.
+/// `x` and `y` are measured in metatiles, and must be less than 16.
+fn write_metatile(video: impl Video, screenblock: usize, x: u16, y: u16, tile_id: u16, palette: u16) {
+	let x = x * 2;
+	let y = y * 2;
+	for (i, (row, col)) in [(y, x), (y, x + 1), (y + 1, x), (y + 1, x + 1)].into_iter().enumerate() {
+		video.set_tile(screenblock, row.into(), col.into(), TileEntry::new(tile_id + i as u16, palette));
+	}
+}
+
+/// Writes the blank tile over a metatile.
+fn clear_metatile(video: impl Video, screenblock: usize, x: u16, y: u16) {
+	let x = x * 2;
+	let y = y * 2;
+	for (row, col) in [(y, x), (y, x + 1), (y + 1, x), (y + 1, x + 1)] {
+		video.set_tile(screenblock, row.into(), col.into(), TileEntry::BLANK);
+	}
+}
+
+/// Fills a screenblock with the blank tile.
+pub fn clear_screenblock(video: impl Video, screenblock: usize) {
+	for row in 0..32 {
+		for col in 0..32 {
+			video.set_tile(screenblock, row, col, TileEntry::BLANK);
 		}
 	}
+}
+
+/// BG2: windows and text.
+pub const TEXT_SCREENBLOCK: usize = 10;
+
+/// Frames between each computer-controlled unit's action.
+const AI_DELAY: u8 = 30;
+
+/// Actions offered by the command menu after a unit moves.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+enum Command {
+	Attack,
+	Staff,
+	Item,
+	Trade,
+	Wait,
+}
+
+impl Command {
+	fn label(self) -> &'static str {
+		match self {
+			Command::Attack => "Attack",
+			Command::Staff => "Staff",
+			Command::Item => "Item",
+			Command::Trade => "Trade",
+			Command::Wait => "Wait",
+		}
+	}
+}
+
+const MAX_COMMANDS: usize = 5;
+
+/// Options offered by the map menu, which opens when A is pressed on an empty tile.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+enum MapOption {
+	/// Saves the chapter and returns to the title screen, to be continued later.
+	Suspend,
+	/// Ends the player's phase.
+	End,
+}
 
-	fn draw(&mut self, oam: &mut Oam, selected: bool) {
-		let sprite = oam.reserve_entry();
-		sprite.0 = ObjAttr0::new()
-			.with_y((self.position.y * 16) as u16);
-		sprite.1 = ObjAttr1::new()
-			.with_x((self.position.x * 16) as u16)
-			.with_size(S16x16);
-		sprite.2 = ObjAttr2::new()
-			.with_tile_id(self.tile_id
-				+ if selected {8} else {0}
-				+ if self.animation_timer & 0x10 != 0 {4} else {0})
-			.with_palbank(self.palette);
-		self.animation_timer = self.animation_timer.wrapping_add(1);
+impl MapOption {
+	fn label(self) -> &'static str {
+		match self {
+			MapOption::Suspend => "Suspend",
+			MapOption::End => "End",
+		}
 	}
 }
 
-pub struct GameState<'a> {
+/// What a unit intends to do to the unit it is targeting.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+enum TargetAction {
+	Attack,
+	/// Heal with the staff in this inventory slot.
+	Staff(usize),
+	Trade,
+}
+
+/// What the player's input is currently controlling.
+/// `origin` is where the acting unit stood before it moved, so that the move can be undone.
+#[derive(Clone, Copy, Debug)]
+enum Mode {
+	/// Moving the cursor freely around the map.
+	Browse,
+	/// A unit is selected, and the cursor picks where it should move.
+	Move { unit: usize },
+	/// The unit has moved, and is choosing what to do from the command menu.
+	Command {
+		unit: usize,
+		origin: Vector2D<i16>,
+		commands: [Command; MAX_COMMANDS],
+		menu: Menu,
+	},
+	/// Choosing an item to use or equip.
+	Items {
+		unit: usize,
+		origin: Vector2D<i16>,
+		menu: Menu,
+	},
+	/// Choosing who to act upon. Attacks show a forecast of the current target.
+	Target {
+		unit: usize,
+		origin: Vector2D<i16>,
+		action: TargetAction,
+		target: usize,
+	},
+	/// Choosing items to pass between a unit and its trade partner.
+	/// Left and right switch between the two inventories; `owner` is the one being shown.
+	Trade {
+		unit: usize,
+		origin: Vector2D<i16>,
+		partner: usize,
+		owner: usize,
+		menu: Menu,
+	},
+	/// Choosing from the map menu.
+	MapMenu { menu: Menu },
+}
+
+pub struct GameState<'a, V: Video> {
+	video: V,
 	cursor: Cursor,
-	units: [Unit; 2],
-	selected_unit: Option<usize>,
+	camera: Camera,
+	/// The metatiles currently written to the map and overlay screenblocks.
+	drawn: Option<TileRect>,
+	/// Tiles highlighted by the overlay, as movement and attack ranges.
+	overlay: Option<(MapBitmap, MapBitmap)>,
+	units: UnitList<'a>,
+	/// Player units which are off the map: those who have escaped, and any from the roster
+	/// who didn't fit on the spawn tiles. They rejoin the roster once the chapter is won.
+	reserve: UnitList<'a>,
+	mode: Mode,
+	turn: TurnState,
+	/// Tiles the selected unit may move to.
+	move_range: Option<MapBitmap>,
+	/// A level up which is being shown to the player.
+	level_up: Option<LevelUp>,
+	/// Frames to wait before the next computer-controlled unit acts.
+	ai_delay: u8,
+	/// Number of player units which have escaped the map.
+	escaped: u8,
+	lord_escaped: bool,
+	result: Option<ChapterResult>,
+	text: TextLayer<V>,
+	pub rng: Rng,
+	/// Where the cursor may go. Clamped to the map by default.
+	pub cursor_bounds: Bounds,
+	/// Stirs the timing of the player's inputs into `rng` as they play.
+	pub mix_input_entropy: bool,
+	/// The campaign chapter being played, if any. Winning it moves on to the next chapter.
+	pub chapter: Option<u8>,
 	tileset_id: u16,
 	tileset_palette: u16,
+	highlight_id: u16,
+	highlight_palette: u16,
 	level: &'a LevelData<'a>
 }
 
-impl<'a> GameState<'a> {
-	pub fn new(level: &'a LevelData) -> Self {
-		let mut vram = Vram::new();
-		vram.load_4bpp_bg_texture(
-			&[0, 0, 0, 0, 0, 0, 0, 0],
-		);
-		let tileset_id = vram.load_4bpp_bg_texture(
-			&include_aligned_resource!("gfx/tree_tiles.4bpp").as_u32_slice(),
-		);
-		let tileset_palette = vram.load_bg_palette(
-			&include_aligned_resource!("gfx/tree_tiles.pal").as_u16_slice(),
-		);
+impl<'a, V: Video> GameState<'a, V> {
+	/// Spawns each of a level's units. Graphics are loaded once the scene is entered.
+	/// Fails if the level has more units than a UnitList can hold.
+	pub fn new(video: V, level: &'a LevelData<'a>) -> Result<Self, RosterFull> {
+		let mut units = UnitList::new();
+		for data in level.units {
+			units.push(Unit::new(data))?;
+		}
+
+		Ok(Self {
+			video,
+			cursor: Cursor::new(),
+			camera: Camera::new(level.width, level.height),
+			drawn: None,
+			overlay: None,
+			units,
+			reserve: UnitList::new(),
+			mode: Mode::Browse,
+			turn: TurnState::new(),
+			move_range: None,
+			level_up: None,
+			ai_delay: AI_DELAY,
+			escaped: 0,
+			lord_escaped: false,
+			result: None,
+			text: TextLayer::new(video, TEXT_SCREENBLOCK),
+			rng: Rng::new(0),
+			cursor_bounds: level.bounds(),
+			mix_input_entropy: true,
+			chapter: None,
+			tileset_id: 0,
+			tileset_palette: 0,
+			highlight_id: 0,
+			highlight_palette: 0,
+			level
+		})
+	}
+
+	/// Begins a level with units carried over from earlier chapters.
+	/// A player unit in the level is replaced by the roster's unit of the same name, keeping the level's position,
+	/// and is left out entirely if `has_fallen` says it died in an earlier chapter.
+	/// The rest of the roster is placed on the level's spawn tiles, and any who don't fit wait in reserve.
+	pub fn with_roster(
+		video: V,
+		level: &'a LevelData<'a>,
+		roster: &UnitList<'a>,
+		has_fallen: impl Fn(&str) -> bool,
+	) -> Result<Self, RosterFull> {
+		let mut game = Self::new(video, level)?;
+		game.units = UnitList::new();
+		// Roster units which have taken the place of one of the level's own.
+		let mut placed = [false; MAX_UNITS];
+		for data in level.units {
+			let veteran = roster.iter().find(|(_, unit)| unit.name == data.name);
+			let unit = match veteran {
+				Some((i, veteran)) if data.faction == Faction::Player => {
+					placed[i] = true;
+					let mut unit = veteran.clone();
+					unit.position = Vector2D { x: data.x as i16, y: data.y as i16 };
+					unit
+				}
+				None if data.faction == Faction::Player && has_fallen(data.name) => continue,
+				_ => Unit::new(data),
+			};
+			game.units.push(unit)?;
+		}
 
-		for y in 0..level.height {
-			for x in 0..level.width {
-				let tile = level.map[(x + y * level.width) as usize] as u16;
-				let x = x * 2;
-				let y = y * 2;
-				TextScreenblockAddress::new(8)
-					.row_col(y.into(), x.into())
-					.write(
-						TextEntry::new()
-							.with_tile(tileset_id + tile * 4 + 0)
-							.with_palbank(tileset_palette)
-					);
-				TextScreenblockAddress::new(8)
-					.row_col(y.into(), (x + 1).into())
-					.write(
-						TextEntry::new()
-							.with_tile(tileset_id + tile * 4 + 1)
-							.with_palbank(tileset_palette)
-					);
-				TextScreenblockAddress::new(8)
-					.row_col((y + 1).into(), x.into())
-					.write(
-						TextEntry::new()
-							.with_tile(tileset_id + tile * 4 + 2)
-							.with_palbank(tileset_palette)
-					);
-				TextScreenblockAddress::new(8)
-					.row_col((y + 1).into(), (x + 1).into())
-					.write(
-						TextEntry::new()
-							.with_tile(tileset_id + tile * 4 + 3)
-							.with_palbank(tileset_palette)
-					);
+		for (i, unit) in roster.iter() {
+			if placed[i] {
+				continue;
+			}
+			let mut unit = unit.clone();
+			match level.spawns.iter().find(|spawn| game.units.at(**spawn).is_none()) {
+				Some(spawn) => {
+					unit.position = *spawn;
+					game.units.push(unit)?;
+				}
+				None => {
+					game.reserve.push(unit)?;
+				}
 			}
 		}
+		Ok(game)
+	}
 
-		Self {
-			cursor: Cursor::new(&mut vram),
-			units: [Unit::new(&mut vram), Unit::new(&mut vram)],
-			selected_unit: None,
-			tileset_id,
-			tileset_palette,
-			level
+	/// Replaces `roster` with every player unit who made it through the chapter, rested and ready for the next one.
+	pub fn survivors(&self, roster: &mut UnitList<'a>) {
+		roster.clear();
+		for (_, unit) in self.units.iter().chain(self.reserve.iter()) {
+			if unit.faction == Faction::Player {
+				let mut unit = unit.clone();
+				unit.hp = unit.stats.hp;
+				unit.has_acted = false;
+				// A roster has room for MAX_UNITS units; any more are left behind.
+				roster.push(unit).ok();
+			}
+		}
+	}
+
+	/// The campaign chapter which follows this one, if there is one.
+	pub fn next_chapter(&self) -> Option<u8> {
+		self.chapter.and_then(|chapter| campaign::CHAPTERS[chapter as usize].next)
+	}
+
+	/// Tints every tile in `move_range` blue and every tile in `attack_range` red.
+	pub fn set_overlay(&mut self, move_range: &MapBitmap, attack_range: &MapBitmap) {
+		self.overlay = Some((move_range.clone(), attack_range.clone()));
+		self.redraw_tiles();
+	}
+
+	/// Removes all highlighted tiles.
+	pub fn clear_overlay(&mut self) {
+		self.overlay = None;
+		clear_screenblock(self.video, OVERLAY_SCREENBLOCK);
+	}
+
+	/// Draws the map and overlay metatiles at a position into their wrapping screenblocks.
+	fn draw_tile(&self, position: Vector2D<i16>) {
+		let x = position.x.rem_euclid(SCREENBLOCK_METATILES) as u16;
+		let y = position.y.rem_euclid(SCREENBLOCK_METATILES) as u16;
+
+		match self.level.tile_at(position) {
+			Some(tile) => write_metatile(
+				self.video,
+				MAP_SCREENBLOCK,
+				x,
+				y,
+				self.tileset_id + assets::gfx::TREE_TILES.tile(tile as usize),
+				self.tileset_palette,
+			),
+			None => clear_metatile(self.video, MAP_SCREENBLOCK, x, y),
+		}
+
+		match &self.overlay {
+			Some((move_range, _)) if move_range.contains(position) => {
+				write_metatile(self.video, OVERLAY_SCREENBLOCK, x, y, self.highlight_id + assets::gfx::HIGHLIGHT.tile(0), self.highlight_palette)
+			}
+			Some((_, attack_range)) if attack_range.contains(position) => {
+				write_metatile(self.video, OVERLAY_SCREENBLOCK, x, y, self.highlight_id + assets::gfx::HIGHLIGHT.tile(1), self.highlight_palette)
+			}
+			_ => clear_metatile(self.video, OVERLAY_SCREENBLOCK, x, y),
 		}
 	}
 
-	pub fn tick(&mut self, input: &Input, oam: &mut Oam) {
-		match input.get_new_x() {
-			Some(AxisX::Left) => self.cursor.position.x -= 1,
-			Some(AxisX::Right) => self.cursor.position.x += 1,
+	/// Draws any metatiles which have scrolled into view since the last frame.
+	/// Only the newly exposed rows and columns are written.
+	fn stream_tiles(&mut self) {
+		let view = self.camera.visible_tiles();
+		for y in view.min.y..view.max.y {
+			for x in view.min.x..view.max.x {
+				let position = Vector2D { x, y };
+				if !self.drawn.is_some_and(|drawn| drawn.contains(position)) {
+					self.draw_tile(position);
+				}
+			}
+		}
+		self.drawn = Some(view);
+	}
+
+	/// Redraws every metatile on screen, such as after the overlay changes.
+	fn redraw_tiles(&mut self) {
+		self.drawn = None;
+		self.stream_tiles();
+	}
+
+	fn movement_range(&self, unit: usize) -> MapBitmap {
+		self.level.movement_range(self.units.get(unit).unwrap(), &self.units)
+	}
+
+	/// Returns true if a unit is allowed to end its move at the given position.
+	fn can_move_to(&self, unit: usize, position: Vector2D<i16>) -> bool {
+		let in_range = match &self.move_range {
+			Some(range) => range.contains(position),
+			None => false,
+		};
+		let occupied = self
+			.units
+			.at(position)
+			.is_some_and(|other| other != unit);
+		in_range && !occupied
+	}
+
+	/// Returns true if a unit belongs to the current phase and hasn't acted yet.
+	fn can_act(&self, unit: usize) -> bool {
+		self.units
+			.get(unit)
+			.is_some_and(|unit| unit.faction == self.turn.phase && !unit.has_acted)
+	}
+
+	/// Ends the current phase, skipping any phases which have no units to act.
+	fn end_phase(&mut self) {
+		for (_, unit) in self.units.iter_mut() {
+			unit.has_acted = false;
+		}
+		// There are only three phases, so give up after checking each of them once.
+		for _ in 0..3 {
+			self.turn.advance();
+			let phase = self.turn.phase;
+			if self.units.iter().any(|(_, unit)| unit.faction == phase) {
+				break;
+			}
+		}
+		self.heal_on_terrain();
+		self.evaluate();
+	}
+
+	/// Restores HP to each unit in the current phase which starts it on healing terrain, such as a fort.
+	fn heal_on_terrain(&mut self) {
+		let phase = self.turn.phase;
+		for (_, unit) in self.units.iter_mut() {
+			if unit.faction != phase {
+				continue;
+			}
+			let Some(terrain) = self.level.terrain_at(unit.position) else {
+				continue;
+			};
+			let amount = unit.stats.hp as u16 * terrain.heal as u16 / 100;
+			unit.heal(amount.min(u8::MAX as u16) as u8);
+		}
+	}
+
+	/// Marks a unit as having acted, deselects it, and ends the phase if nobody is left to act.
+	fn finish_action(&mut self, unit: usize) {
+		if let Some(unit) = self.units.get_mut(unit) {
+			unit.has_acted = true;
+		}
+		self.mode = Mode::Browse;
+		self.move_range = None;
+		self.clear_overlay();
+		self.text.clear();
+
+		if let Objective::Escape(exit) = self.level.objective {
+			if self
+				.units
+				.get(unit)
+				.is_some_and(|unit| unit.faction == Faction::Player && unit.position == exit)
+			{
+				let unit = self.units.remove(unit).unwrap();
+				self.escaped += 1;
+				self.lord_escaped |= self.level.defeat.lord == Some(unit.name);
+				// Units only leave the map by moving, so the reserve always has room for them.
+				self.reserve.push(unit).ok();
+			}
+		}
+
+		self.evaluate();
+		if self.result.is_some() {
+			return;
+		}
+
+		let phase = self.turn.phase;
+		if self
+			.units
+			.iter()
+			.all(|(_, unit)| unit.faction != phase || unit.has_acted)
+		{
+			self.end_phase();
+		}
+	}
+
+	/// Checks whether the chapter has been won or lost, and announces the result if so.
+	/// Victory is checked first, so that escaping with the last unit isn't mistaken for losing it.
+	fn evaluate(&mut self) {
+		if self.result.is_some() {
+			return;
+		}
+		let level = self.level;
+		self.result = if level.objective.is_complete(
+			&self.units,
+			&self.turn,
+			level.defeat.lord,
+			(self.escaped, self.lord_escaped),
+		) {
+			Some(ChapterResult::Victory)
+		} else if level.defeat.is_lost(&self.units, &self.turn, self.lord_escaped) {
+			Some(ChapterResult::Defeat)
+		} else {
+			None
+		};
+
+		if self.result.is_some() {
+			self.mode = Mode::Browse;
+			self.draw_result();
+		}
+	}
+
+	fn draw_result(&self) {
+		let Some(result) = self.result else {
+			return;
+		};
+		self.text.clear();
+		self.text.fill(8, 8, 14, 3);
+		let message = match result {
+			ChapterResult::Victory => "Chapter Clear",
+			ChapterResult::Defeat => "  Game Over",
+		};
+		write!(self.text.at(9, 9), "{message}").ok();
+	}
+
+	/// Records everything needed to resume the chapter later. Any unfinished action is abandoned.
+	pub fn write_suspend(&self, writer: &mut Writer) {
+		save::write_rng(writer, &self.rng);
+		writer.u16(self.turn.turn);
+		writer.u8(self.turn.phase as u8);
+		writer.u8(self.escaped);
+		writer.bool(self.lord_escaped);
+		writer.i16(self.cursor.position.x);
+		writer.i16(self.cursor.position.y);
+		save::write_units(writer, &self.units);
+		save::write_units(writer, &self.reserve);
+	}
+
+	/// Restores a chapter recorded by `write_suspend`.
+	/// Unit names are looked up in `names`, which must include every unit in the level and the roster.
+	pub fn read_suspend(
+		video: V,
+		level: &'a LevelData<'a>,
+		names: &[&'a str],
+		reader: &mut Reader,
+	) -> Result<Self, SaveError> {
+		let mut game = Self::new(video, level).map_err(|_| SaveError::Corrupt)?;
+		game.rng = save::read_rng(reader)?;
+		game.turn.turn = reader.u16()?;
+		game.turn.phase = save::read_faction(reader)?;
+		game.escaped = reader.u8()?;
+		game.lord_escaped = reader.bool()?;
+		game.cursor.position.x = reader.i16()?;
+		game.cursor.position.y = reader.i16()?;
+		save::read_units(reader, names, &mut game.units)?;
+		save::read_units(reader, names, &mut game.reserve)?;
+		if game.units.iter().any(|(_, unit)| !level.bounds().contains(unit.position))
+			|| !level.bounds().contains(game.cursor.position)
+		{
+			return Err(SaveError::Corrupt);
+		}
+		Ok(game)
+	}
+
+	/// Returns whether the chapter has been won or lost, once it is over.
+	pub fn result(&self) -> Option<ChapterResult> {
+		self.result
+	}
+
+	pub fn units(&self) -> &UnitList<'a> {
+		&self.units
+	}
+
+	/// The current turn number and whose phase it is.
+	pub fn turn(&self) -> &TurnState {
+		&self.turn
+	}
+
+	pub fn cursor_position(&self) -> Vector2D<i16> {
+		self.cursor.position
+	}
+
+	/// Describes a unit for the combat engine.
+	fn combatant<'u>(&self, unit: &'u Unit) -> Combatant<'u> {
+		Combatant {
+			stats: &unit.stats,
+			hp: unit.hp,
+			weapon: unit.equipped_weapon(),
+			terrain: self
+				.level
+				.terrain_at(unit.position)
+				.expect("units must stand within the map"),
+		}
+	}
+
+	fn forecast(&self, attacker: usize, defender: usize) -> Option<Forecast> {
+		let attacker = self.units.get(attacker)?;
+		let defender = self.units.get(defender)?;
+		Some(combat::forecast(
+			&self.combatant(attacker),
+			&self.combatant(defender),
+			attacker.position.manhattan_distance(defender.position) as u8,
+		))
+	}
+
+	/// Finds the closest tile to the attacker which it can reach and strike the target from.
+	fn attack_position(&self, attacker: usize, target: usize) -> Option<Vector2D<i16>> {
+		let unit = self.units.get(attacker)?;
+		let weapon = unit.equipped_weapon()?;
+		let target = self.units.get(target)?.position;
+		self.move_range
+			.as_ref()?
+			.iter()
+			.filter(|position| self.units.at(*position).is_none_or(|other| other == attacker))
+			.filter(|position| {
+				let distance = position.manhattan_distance(target);
+				distance <= u8::MAX as u16 && weapon.in_range(distance as u8)
+			})
+			.min_by_key(|position| position.manhattan_distance(unit.position))
+	}
+
+	/// Returns true if `unit` may perform `action` on `target` from where it stands.
+	fn is_target(&self, unit: usize, action: TargetAction, target: usize) -> bool {
+		let (Some(actor), Some(other)) = (self.units.get(unit), self.units.get(target)) else {
+			return false;
+		};
+		if unit == target {
+			return false;
+		}
+		let distance = actor.position.manhattan_distance(other.position);
+		match action {
+			TargetAction::Attack => {
+				actor.faction.is_hostile_to(other.faction)
+					&& actor
+						.equipped_weapon()
+						.is_some_and(|weapon| distance <= u8::MAX as u16 && weapon.in_range(distance as u8))
+			}
+			TargetAction::Staff(slot) => {
+				let Some(staff) = actor.inventory.get(slot).map(|item| item.data()) else {
+					return false;
+				};
+				actor.can_use_staff(staff.kind)
+					&& !actor.faction.is_hostile_to(other.faction)
+					&& other.hp < other.stats.hp
+					&& (staff.min_range as u16..=staff.max_range as u16).contains(&distance)
+			}
+			TargetAction::Trade => actor.faction == other.faction && distance == 1,
+		}
+	}
+
+	/// Finds the next valid target after `current`, wrapping around the unit list.
+	/// Passing None finds the first target.
+	fn next_target(&self, unit: usize, action: TargetAction, current: Option<usize>, forward: bool) -> Option<usize> {
+		let start = current.map_or(if forward { MAX_UNITS - 1 } else { 0 }, |i| i);
+		(1..=MAX_UNITS)
+			.map(|step| {
+				if forward {
+					(start + step) % MAX_UNITS
+				} else {
+					(start + MAX_UNITS - step) % MAX_UNITS
+				}
+			})
+			.find(|i| self.is_target(unit, action, *i))
+	}
+
+	/// Returns the first staff a unit has that can heal someone in range, as a target action.
+	fn staff_action(&self, unit: usize) -> Option<TargetAction> {
+		self.units
+			.get(unit)?
+			.inventory
+			.iter()
+			.map(|(slot, _)| TargetAction::Staff(slot))
+			.find(|action| self.next_target(unit, *action, None, true).is_some())
+	}
+
+	/// Lists the commands available to a unit where it stands.
+	fn commands(&self, unit: usize) -> ([Command; MAX_COMMANDS], usize) {
+		let mut commands = [Command::Wait; MAX_COMMANDS];
+		let mut len = 0;
+		let mut offer = |command| {
+			commands[len] = command;
+			len += 1;
+		};
+
+		if self.next_target(unit, TargetAction::Attack, None, true).is_some() {
+			offer(Command::Attack);
+		}
+		if self.staff_action(unit).is_some() {
+			offer(Command::Staff);
+		}
+		if self.units.get(unit).is_some_and(|unit| !unit.inventory.is_empty()) {
+			offer(Command::Item);
+		}
+		if self.next_target(unit, TargetAction::Trade, None, true).is_some() {
+			offer(Command::Trade);
+		}
+		offer(Command::Wait);
+
+		(commands, len)
+	}
+
+	fn open_commands(&mut self, unit: usize, origin: Vector2D<i16>) {
+		let (commands, len) = self.commands(unit);
+		let menu = Menu::new(len).with_edge(Edge::Wrap);
+		self.text.clear();
+		self.draw_commands(&commands, &menu);
+		self.mode = Mode::Command {
+			unit,
+			origin,
+			commands,
+			menu,
+		};
+	}
+
+	fn draw_commands(&self, commands: &[Command; MAX_COMMANDS], menu: &Menu) {
+		menu.draw(&self.text, 21, 1, 8, |i, text| {
+			write!(text, "{}", commands[i].label()).ok();
+		});
+	}
+
+	fn draw_items(&self, unit: usize, menu: &Menu) {
+		let Some(unit) = self.units.get(unit) else {
+			return;
+		};
+		menu.draw(&self.text, 12, 1, 17, |i, text| {
+			if let Some(item) = unit.inventory.get(i) {
+				write!(text, "{:<11.11} {:>2}", item.data().name, item.uses).ok();
+			}
+		});
+	}
+
+	/// Draws the items of whichever side of a trade is being shown, under its owner's name.
+	fn draw_trade(&self, owner: usize, menu: &Menu) {
+		if let Some(unit) = self.units.get(owner) {
+			write!(self.text.at(13, 0), "{:<16.16}", unit.name).ok();
+		}
+		self.draw_items(owner, menu);
+	}
+
+	/// Points the cursor at a target and shows what would happen to it.
+	fn show_target(&mut self, unit: usize, action: TargetAction, target: usize) {
+		self.text.clear();
+		if let Some(target) = self.units.get(target) {
+			self.cursor.position = target.position;
+		}
+		if action == TargetAction::Attack {
+			if let (Some(forecast), Some(attacker), Some(defender)) =
+				(self.forecast(unit, target), self.units.get(unit), self.units.get(target))
+			{
+				self.draw_forecast(attacker, defender, &forecast);
+			}
+		}
+	}
+
+	/// Undoes a unit's move, returning it to the tile it started on and reselecting it.
+	fn cancel_move(&mut self, unit: usize, origin: Vector2D<i16>) {
+		self.text.clear();
+		if let Some(unit) = self.units.get_mut(unit) {
+			unit.position = origin;
+		}
+		self.cursor.position = origin;
+		self.select(unit);
+	}
+
+	/// Selects a unit to move, and highlights its range.
+	fn select(&mut self, unit: usize) {
+		let move_range = self.movement_range(unit);
+		let attack_range = match self.units.get(unit).and_then(|unit| unit.equipped_weapon()) {
+			Some(weapon) => attack_range(&move_range, weapon.min_range, weapon.max_range),
+			None => MapBitmap::new(self.level.width, self.level.height),
+		};
+		self.set_overlay(&move_range, &attack_range);
+		self.move_range = Some(move_range);
+		self.mode = Mode::Move { unit };
+	}
+
+	fn draw_forecast(&self, attacker: &Unit, defender: &Unit, forecast: &Forecast) {
+		fn follow_up(attacks: u8) -> &'static str {
+			if attacks > 1 { "x2" } else { "" }
+		}
+
+		self.text.fill(1, 1, 14, 7);
+		write!(
+			self.text.at(2, 2),
+			"{:<6.6}{:>6.6}\nHP  {:>4}{:>4}\nDmg {:>2}{:<2}{:>2}{:<2}\nHit {:>4}{:>4}\nCrt {:>4}{:>4}",
+			attacker.name,
+			defender.name,
+			forecast.attacker.hp,
+			forecast.defender.hp,
+			forecast.attacker.damage,
+			follow_up(forecast.attacker.attacks),
+			forecast.defender.damage,
+			follow_up(forecast.defender.attacks),
+			forecast.attacker.hit,
+			forecast.defender.hit,
+			forecast.attacker.crit,
+			forecast.defender.crit,
+		)
+		.ok();
+	}
+
+	/// Plays out a battle between two units, removing any that fall.
+	fn perform_attack(&mut self, attacker: usize, defender: usize) {
+		let Some(forecast) = self.forecast(attacker, defender) else {
+			return;
+		};
+		let result = combat::resolve(&forecast, &mut self.rng);
+
+		let strikes = |side| result.strikes().iter().filter(|strike| strike.side == side).count();
+		if let Some(unit) = self.units.get_mut(attacker) {
+			unit.hp = result.attacker_hp;
+			unit.wear_weapon(strikes(Side::Attacker));
+		}
+		if let Some(unit) = self.units.get_mut(defender) {
+			unit.hp = result.defender_hp;
+			unit.wear_weapon(strikes(Side::Defender));
+		}
+
+		// Surviving player units earn experience based on how the fight went for them.
+		let mut level_up = None;
+		for (side, i, foe) in [
+			(Side::Attacker, attacker, defender),
+			(Side::Defender, defender, attacker),
+		] {
+			let (Some(unit), Some(foe)) = (self.units.get(i), self.units.get(foe)) else {
+				continue;
+			};
+			if unit.faction != Faction::Player || unit.hp == 0 {
+				continue;
+			}
+			let outcome = if foe.hp == 0 {
+				CombatOutcome::Defeated
+			} else if result.strikes().iter().any(|strike| strike.side == side && strike.hit) {
+				CombatOutcome::Damaged
+			} else {
+				CombatOutcome::Missed
+			};
+			let exp = experience::combat_exp(unit.level, foe.level, foe.is_boss, outcome);
+			let unit = self.units.get_mut(i).unwrap();
+			let name = unit.name;
+			if let Some(result) = unit.gain_exp(exp, &mut self.rng) {
+				level_up = Some((name, result));
+			}
+		}
+
+		for i in [attacker, defender] {
+			if self.units.get(i).is_some_and(|unit| unit.hp == 0) {
+				self.units.remove(i);
+			}
+		}
+
+		self.finish_action(attacker);
+		if let Some((name, level_up)) = level_up {
+			self.show_level_up(name, level_up);
+		}
+	}
+
+	/// Heals `target` with the staff in the healer's `slot`, spending one of its uses,
+	/// awarding the healer experience, and ending its action. Returns false if the staff can't be used.
+	fn heal_with_staff(&mut self, healer: usize, slot: usize, target: usize) -> bool {
+		if !self.is_target(healer, TargetAction::Staff(slot), target) {
+			return false;
+		}
+		let (Some(unit), Some(patient)) = (self.units.get(healer), self.units.get(target)) else {
+			return false;
+		};
+		let heal = unit.inventory.get(slot).map_or(0, |item| item.data().heal);
+		let exp = experience::heal_exp(unit.level, patient.level);
+		self.units.get_mut(target).unwrap().heal(heal);
+
+		let unit = self.units.get_mut(healer).unwrap();
+		unit.inventory.spend(slot);
+		let name = unit.name;
+		let level_up = if unit.faction == Faction::Player {
+			unit.gain_exp(exp, &mut self.rng)
+		} else {
+			None
+		};
+
+		self.finish_action(healer);
+		if let Some(level_up) = level_up {
+			self.show_level_up(name, level_up);
+		}
+		true
+	}
+
+	/// Moves the item in `giver`'s `slot` over to `receiver`, if it has room.
+	fn give_item(&mut self, giver: usize, slot: usize, receiver: usize) {
+		let Some(item) = self.units.get(giver).and_then(|giver| giver.inventory.get(slot).copied()) else {
+			return;
+		};
+		let Some(receiver) = self.units.get_mut(receiver) else {
+			return;
+		};
+		if receiver.inventory.push(item).is_ok() {
+			if receiver.equipped_slot().is_none() {
+				receiver.equip_first_weapon();
+			}
+			let giver = self.units.get_mut(giver).unwrap();
+			giver.inventory.remove(slot);
+			if giver.equipped_slot().is_none() {
+				giver.equip_first_weapon();
+			}
+		}
+	}
+
+	fn show_level_up(&mut self, name: &str, level_up: LevelUp) {
+		// Don't cover up the end of the chapter.
+		if self.result.is_some() {
+			return;
+		}
+		self.draw_level_up(name, &level_up);
+		self.level_up = Some(level_up);
+	}
+
+	fn draw_level_up(&self, name: &str, level_up: &LevelUp) {
+		let after = level_up.after();
+		let gains = level_up.gains;
+		self.text.fill(1, 1, 16, 9);
+		write!(
+			self.text.at(2, 2),
+			"{:<8.8}  Lv{:>2}\n\
+			HP {:>2}{:<2} Lck{:>2}{:<2}\n\
+			Str{:>2}{:<2} Def{:>2}{:<2}\n\
+			Mag{:>2}{:<2} Res{:>2}{:<2}\n\
+			Skl{:>2}{:<2}\n\
+			Spd{:>2}{:<2}",
+			name,
+			level_up.level,
+			after.hp,
+			increase(gains.hp),
+			after.luck,
+			increase(gains.luck),
+			after.strength,
+			increase(gains.strength),
+			after.defense,
+			increase(gains.defense),
+			after.magic,
+			increase(gains.magic),
+			after.resistance,
+			increase(gains.resistance),
+			after.skill,
+			increase(gains.skill),
+			after.speed,
+			increase(gains.speed),
+		)
+		.ok();
+
+		fn increase(gain: u8) -> &'static str {
+			match gain {
+				0 => "",
+				1 => "+1",
+				2 => "+2",
+				_ => "++",
+			}
+		}
+	}
+
+	/// Lets the next computer-controlled unit act, pausing between each one so the player can follow along.
+	fn update_ai(&mut self) {
+		if self.ai_delay > 0 {
+			self.ai_delay -= 1;
+			return;
+		}
+		self.ai_delay = AI_DELAY;
+
+		let phase = self.turn.phase;
+		let Some(unit) = self
+			.units
+			.iter()
+			.find(|(_, unit)| unit.faction == phase && !unit.has_acted)
+			.map(|(i, _)| i)
+		else {
+			self.end_phase();
+			return;
+		};
+
+		let decision = ai::decide(self.level, &self.units, unit);
+		if let Some(decision) = decision {
+			self.units.get_mut(unit).unwrap().position = decision.destination;
+			self.cursor.position = decision.destination;
+		}
+		match decision {
+			Some(Decision {
+				target: Some(target), ..
+			}) => self.perform_attack(unit, target),
+			Some(Decision {
+				item: Some(slot), ..
+			}) => {
+				self.units.get_mut(unit).unwrap().use_item(slot);
+				self.finish_action(unit);
+			}
+			_ => self.finish_action(unit),
+		}
+	}
+
+	/// Moves the cursor with the d-pad, keeping it within `cursor_bounds`.
+	fn move_cursor(&mut self, input: &Input) {
+		let mut offset = Vector2D { x: 0, y: 0 };
+		match input.get_repeated_x() {
+			Some(AxisX::Left) => offset.x = -1,
+			Some(AxisX::Right) => offset.x = 1,
 			_ => {}
 		}
 
-		match input.get_new_y() {
-			Some(AxisY::Up) => self.cursor.position.y -= 1,
-			Some(AxisY::Down) => self.cursor.position.y += 1,
+		match input.get_repeated_y() {
+			Some(AxisY::Up) => offset.y = -1,
+			Some(AxisY::Down) => offset.y = 1,
 			_ => {}
 		}
 
-		if input.new.a() {
-			if let Some(selected_unit) = self.selected_unit {
-				self.units[selected_unit].position = self.cursor.position;
-				self.selected_unit = None;
-			} else {
-				for (i, unit) in self.units.iter().enumerate() {
-					if self.cursor.position == unit.position {
-						self.selected_unit = Some(i);
-						break;
+		self.cursor.position = self.cursor_bounds.step(self.cursor.position, offset);
+	}
+
+	fn update_mode(&mut self, input: &Input) {
+		match self.mode {
+			Mode::Browse => {
+				self.move_cursor(input);
+				if input.new.a() {
+					if let Some(unit) = self
+						.units
+						.at(self.cursor.position)
+						.filter(|i| self.can_act(*i))
+					{
+						self.select(unit);
+					} else if self.units.at(self.cursor.position).is_none() {
+						self.open_map_menu();
+					}
+				} else if input.new.start() {
+					self.end_phase();
+				}
+			}
+			Mode::Move { unit } => {
+				self.move_cursor(input);
+				if input.new.a() {
+					let Some(origin) = self.units.get(unit).map(|unit| unit.position) else {
+						return;
+					};
+					let target = self
+						.units
+						.at(self.cursor.position)
+						.filter(|target| self.is_hostile(unit, *target));
+					if let Some(target) = target {
+						// Selecting an enemy moves into range and jumps straight to its forecast.
+						if let Some(from) = self.attack_position(unit, target) {
+							self.units.get_mut(unit).unwrap().position = from;
+							self.clear_overlay();
+							self.show_target(unit, TargetAction::Attack, target);
+							self.mode = Mode::Target {
+								unit,
+								origin,
+								action: TargetAction::Attack,
+								target,
+							};
+						}
+					} else if self.can_move_to(unit, self.cursor.position) {
+						self.units.get_mut(unit).unwrap().position = self.cursor.position;
+						self.clear_overlay();
+						self.open_commands(unit, origin);
 					}
+				} else if input.new.b() {
+					self.mode = Mode::Browse;
+					self.move_range = None;
+					self.clear_overlay();
+				}
+			}
+			Mode::Command {
+				unit,
+				origin,
+				commands,
+				mut menu,
+			} => {
+				let event = menu.update(input);
+				self.mode = Mode::Command {
+					unit,
+					origin,
+					commands,
+					menu,
+				};
+				match event {
+					None => self.draw_commands(&commands, &menu),
+					Some(MenuEvent::Cancel) => self.cancel_move(unit, origin),
+					Some(MenuEvent::Confirm(i)) => match commands[i] {
+						Command::Attack => self.begin_targeting(unit, origin, TargetAction::Attack),
+						Command::Staff => {
+							if let Some(action) = self.staff_action(unit) {
+								self.begin_targeting(unit, origin, action);
+							}
+						}
+						Command::Trade => self.begin_targeting(unit, origin, TargetAction::Trade),
+						Command::Item => {
+							let len = self.units.get(unit).map_or(0, |unit| unit.inventory.len());
+							let menu = Menu::new(len);
+							self.text.clear();
+							self.draw_items(unit, &menu);
+							self.mode = Mode::Items { unit, origin, menu };
+						}
+						Command::Wait => self.finish_action(unit),
+					},
 				}
 			}
+			Mode::Items {
+				unit,
+				origin,
+				mut menu,
+			} => match menu.update(input) {
+				None => {
+					self.draw_items(unit, &menu);
+					self.mode = Mode::Items { unit, origin, menu };
+				}
+				Some(MenuEvent::Cancel) => self.open_commands(unit, origin),
+				Some(MenuEvent::Confirm(slot)) => {
+					let Some(actor) = self.units.get_mut(unit) else {
+						return;
+					};
+					if actor.use_item(slot) {
+						self.finish_action(unit);
+					} else if actor.equip(slot) {
+						self.open_commands(unit, origin);
+					}
+				}
+			},
+			Mode::Target {
+				unit,
+				origin,
+				action,
+				target,
+			} => {
+				let forward = match input.get_new_direction4() {
+					Some(Direction4::Right | Direction4::Down) => Some(true),
+					Some(Direction4::Left | Direction4::Up) => Some(false),
+					None => None,
+				};
+				if let Some(forward) = forward {
+					if let Some(next) = self.next_target(unit, action, Some(target), forward) {
+						self.show_target(unit, action, next);
+						self.mode = Mode::Target {
+							unit,
+							origin,
+							action,
+							target: next,
+						};
+					}
+				} else if input.new.a() {
+					match action {
+						TargetAction::Attack => self.perform_attack(unit, target),
+						TargetAction::Staff(slot) => {
+							self.heal_with_staff(unit, slot, target);
+						}
+						TargetAction::Trade => {
+							let len = self.units.get(unit).map_or(0, |unit| unit.inventory.len());
+							let menu = Menu::new(len);
+							self.text.clear();
+							self.draw_trade(unit, &menu);
+							self.mode = Mode::Trade {
+								unit,
+								origin,
+								partner: target,
+								owner: unit,
+								menu,
+							};
+						}
+					}
+				} else if input.new.b() {
+					if let Some(actor) = self.units.get(unit) {
+						self.cursor.position = actor.position;
+					}
+					self.open_commands(unit, origin);
+				}
+			}
+			Mode::Trade {
+				unit,
+				origin,
+				partner,
+				mut owner,
+				mut menu,
+			} => {
+				if input.new.left() || input.new.right() {
+					owner = if input.new.left() { unit } else { partner };
+					menu = Menu::new(self.units.get(owner).map_or(0, |owner| owner.inventory.len()));
+					self.text.clear();
+				}
+				let other = if owner == unit { partner } else { unit };
+				match menu.update(input) {
+					Some(MenuEvent::Cancel) => {
+						if let Some(actor) = self.units.get(unit) {
+							self.cursor.position = actor.position;
+						}
+						self.open_commands(unit, origin);
+						return;
+					}
+					Some(MenuEvent::Confirm(slot)) => {
+						self.give_item(owner, slot, other);
+						let len = self.units.get(owner).map_or(0, |owner| owner.inventory.len());
+						menu.resize(len);
+						self.text.clear();
+					}
+					None => {}
+				}
+				self.draw_trade(owner, &menu);
+				self.mode = Mode::Trade {
+					unit,
+					origin,
+					partner,
+					owner,
+					menu,
+				};
+			}
+			// Suspending leaves the chapter, so the map menu is updated by `tick` instead.
+			Mode::MapMenu { .. } => {}
 		}
+	}
 
-		{
-			let mut cursor_state = CursorState::Idle;
-			for unit in &self.units {
-				if self.cursor.position == unit.position {
-					cursor_state = CursorState::Open;
-				}
+	/// Suspending is only offered during a campaign, which has somewhere to return to.
+	fn map_options(&self) -> &'static [MapOption] {
+		if self.chapter.is_some() {
+			&[MapOption::Suspend, MapOption::End]
+		} else {
+			&[MapOption::End]
+		}
+	}
+
+	fn open_map_menu(&mut self) {
+		let menu = Menu::new(self.map_options().len()).with_edge(Edge::Wrap);
+		self.text.clear();
+		self.draw_map_menu(&menu);
+		self.mode = Mode::MapMenu { menu };
+	}
+
+	fn draw_map_menu(&self, menu: &Menu) {
+		let options = self.map_options();
+		menu.draw(&self.text, 21, 1, 8, |i, text| {
+			write!(text, "{}", options[i].label()).ok();
+		});
+	}
+
+	/// Unlike the other modes, the map menu can leave the chapter, so it returns a transition.
+	fn update_map_menu(&mut self, mut menu: Menu, input: &Input) -> Transition<'a, V> {
+		let Some(event) = menu.update(input) else {
+			self.draw_map_menu(&menu);
+			self.mode = Mode::MapMenu { menu };
+			return Transition::None;
+		};
+		self.text.clear();
+		self.mode = Mode::Browse;
+		match event {
+			MenuEvent::Confirm(i) if self.map_options()[i] == MapOption::Suspend => {
+				Transition::Replace(suspend_chapter)
 			}
-			if self.selected_unit.is_some() {
-				cursor_state = CursorState::Closed;
+			MenuEvent::Confirm(_) => {
+				self.end_phase();
+				Transition::None
 			}
-			self.cursor.draw(oam, cursor_state);
+			MenuEvent::Cancel => Transition::None,
 		}
+	}
 
-		for (i, unit) in self.units.iter_mut().enumerate() {
-			unit.draw(oam, Some(i) == self.selected_unit);
+	fn is_hostile(&self, unit: usize, other: usize) -> bool {
+		match (self.units.get(unit), self.units.get(other)) {
+			(Some(unit), Some(other)) => unit.faction.is_hostile_to(other.faction),
+			_ => false,
 		}
 	}
+
+	/// Switches to target selection, starting with the first valid target.
+	fn begin_targeting(&mut self, unit: usize, origin: Vector2D<i16>, action: TargetAction) {
+		if let Some(target) = self.next_target(unit, action, None, true) {
+			self.show_target(unit, action, target);
+			self.mode = Mode::Target {
+				unit,
+				origin,
+				action,
+				target,
+			};
+		}
+	}
+}
+
+impl<'a, V: Video> Scene<'a, V> for GameState<'a, V> {
+	fn enter(&mut self, vram: &mut Vram<V>) {
+		vram.load_4bpp_bg_texture(
+			&[0, 0, 0, 0, 0, 0, 0, 0],
+		);
+		let tileset = assets::gfx::TREE_TILES;
+		self.tileset_id = vram.load_4bpp_bg_texture(tileset.tiles());
+		self.tileset_palette = vram.load_bg_palette(tileset.palette());
+
+		let highlight = assets::gfx::HIGHLIGHT;
+		self.highlight_id = vram.load_4bpp_bg_texture(highlight.tiles());
+		self.highlight_palette = vram.load_bg_palette(highlight.palette());
+
+		self.camera.snap(self.cursor.position);
+		self.redraw_tiles();
+
+		self.text.load(vram);
+		self.cursor.load(vram);
+
+		// Each class's sprite is only loaded once, by the first unit that needs it.
+		let mut sprites: [Option<UnitSprite>; CLASS_COUNT] = [None; CLASS_COUNT];
+		for (_, unit) in self.units.iter_mut() {
+			unit.sprite = *sprites[unit.class as usize].get_or_insert_with(|| {
+				UnitSprite::load(vram, &unit.class.data().sprite)
+			});
+		}
+
+		// Menus and windows redraw themselves, but anything shown only once must be restored.
+		self.draw_result();
+	}
+
+	fn tick(&mut self, input: &Input) -> Transition<'a, V> {
+		if self.mix_input_entropy && input.any_new() {
+			self.rng.mix(input.entropy());
+		}
+
+		if self.result.is_some() {
+			// The chapter is over, so hand control back to whoever started it.
+			if input.new.a() || input.new.start() {
+				return match (self.result, self.next_chapter()) {
+					(Some(ChapterResult::Victory), Some(_)) => Transition::Replace(introduce_next_chapter),
+					_ => Transition::Replace(return_to_title),
+				};
+			}
+		} else if self.level_up.is_some() {
+			if input.new.a() || input.new.b() {
+				self.level_up = None;
+				self.text.clear();
+			}
+		} else if let (Faction::Player, Mode::MapMenu { menu }) = (self.turn.phase, self.mode) {
+			return self.update_map_menu(menu, input);
+		} else if self.turn.phase == Faction::Player {
+			self.update_mode(input);
+		} else {
+			self.update_ai();
+		}
+		Transition::None
+	}
+
+	fn draw(&mut self, oam: &mut Oam) {
+		self.camera.follow(self.cursor.position);
+		self.stream_tiles();
+		for background in [0, 1] {
+			self.video.set_scroll(
+				background,
+				self.camera.position.x as u16,
+				self.camera.position.y as u16,
+			);
+		}
+
+		let cursor_state = match self.mode {
+			Mode::Browse if self.units.at(self.cursor.position).is_some() => CursorState::Open,
+			Mode::Browse => CursorState::Idle,
+			_ => CursorState::Closed,
+		};
+		self.cursor.draw(oam, cursor_state, &self.camera);
+
+		let selected = match self.mode {
+			Mode::Browse | Mode::MapMenu { .. } => None,
+			Mode::Move { unit }
+			| Mode::Command { unit, .. }
+			| Mode::Items { unit, .. }
+			| Mode::Target { unit, .. }
+			| Mode::Trade { unit, .. } => Some(unit),
+		};
+		for (i, unit) in self.units.iter_mut() {
+			unit.draw(oam, Some(i) == selected, &self.camera);
+		}
+	}
+}
+
+/// Saves the campaign and introduces the chapter after the one just won, carrying over its survivors.
+fn introduce_next_chapter<V: Video>(scenes: &mut Scenes<V>) -> SceneKind {
+	let game = scenes.map.as_ref().expect("the next chapter follows a map");
+	let campaign = CampaignSave {
+		chapter: game.next_chapter().expect("the campaign is over"),
+		rng: game.rng,
+	};
+	game.survivors(&mut scenes.roster);
+	if let Some(storage) = scenes.storage.as_deref_mut() {
+		// A save which fails to write is no reason to stop playing, so errors are ignored.
+		save::save_campaign(storage, CAMPAIGN_SLOT, &campaign, &scenes.roster).ok();
+		save::erase_slot(storage, SUSPEND_SLOT).ok();
+	}
+	scenes.intro = Some(ChapterIntro::new(scenes.video, campaign.chapter, campaign.rng));
+	SceneKind::Intro
+}
+
+/// Returns to the title screen once the campaign is over, or lost.
+/// Any suspend of the chapter is erased, so that continuing starts the chapter over.
+fn return_to_title<V: Video>(scenes: &mut Scenes<V>) -> SceneKind {
+	if let Some(storage) = scenes.storage.as_deref_mut() {
+		save::erase_slot(storage, SUSPEND_SLOT).ok();
+	}
+	title::show_title(scenes)
+}
+
+/// Suspends the chapter and returns to the title screen. If it can't be saved, the chapter carries on instead.
+fn suspend_chapter<V: Video>(scenes: &mut Scenes<V>) -> SceneKind {
+	let game = scenes.map.as_ref().expect("only a map can be suspended");
+	let suspended = match (scenes.storage.as_deref_mut(), game.chapter) {
+		(Some(storage), Some(chapter)) => save::suspend(storage, chapter, game).is_ok(),
+		_ => false,
+	};
+	if suspended {
+		title::show_title(scenes)
+	} else {
+		SceneKind::Map
+	}
 }
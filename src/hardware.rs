@@ -0,0 +1,159 @@
+pub use gba::bios::VBlankIntrWait as wait_vblank;
+
+use furry_emblem::console::{KeySource, Keys};
+use furry_emblem::save::{Storage, SRAM_SIZE};
+use furry_emblem::video::{Sprite, SpriteSize, TileEntry, Video};
+use gba::keys::KeyInput;
+use gba::mmio::{self, TextScreenblockAddress};
+use gba::video::obj::{ObjAttr0, ObjAttr1, ObjAttr2, ObjDisplayStyle};
+use gba::video::{Color, TextEntry};
+use voladdress::{Safe, VolBlock};
+
+pub const VRAM_BLOCK0: VolBlock<u32, Safe, Safe, 0x1000> = unsafe { VolBlock::new(0x06000000) };
+pub const VRAM_OBJS: VolBlock<u32, Safe, Safe, 0x1000> = unsafe { VolBlock::new(0x06010000) };
+
+/// Battery-backed SRAM. It sits on an 8-bit bus, so it must only ever be accessed a byte at a time.
+pub const SRAM: VolBlock<u8, Safe, Safe, SRAM_SIZE> = unsafe { VolBlock::new(0x0E000000) };
+
+/// Formats and prints a message to the emulator.
+/// The message is marked as "Info".
+macro_rules! println {
+	($($args:expr),+) => {{
+		let log_level = gba::mgba::MgbaMessageLevel::Info;
+		if let Ok(mut logger) = gba::prelude::MgbaBufferedLogger::try_new(log_level) {
+			writeln!(logger, $($args),+).ok();
+		}
+	}}
+}
+
+pub(crate) use println;
+
+/// The GBA's PPU, driven through its memory-mapped registers.
+#[derive(Clone, Copy)]
+pub struct Gba;
+
+impl Video for Gba {
+	fn load_bg_tiles(&self, index: usize, data: &[u32]) {
+		for (i, word) in data.iter().enumerate() {
+			VRAM_BLOCK0.index(index * 8 + i).write(*word);
+		}
+	}
+
+	fn load_obj_tiles(&self, index: usize, data: &[u32]) {
+		for (i, word) in data.iter().enumerate() {
+			VRAM_OBJS.index(index * 8 + i).write(*word);
+		}
+	}
+
+	fn load_bg_colors(&self, index: usize, colors: &[u16]) {
+		for (i, color) in colors.iter().enumerate() {
+			mmio::BG_PALETTE.index(index + i).write(Color(*color));
+		}
+	}
+
+	fn load_obj_colors(&self, index: usize, colors: &[u16]) {
+		for (i, color) in colors.iter().enumerate() {
+			mmio::OBJ_PALETTE.index(index + i).write(Color(*color));
+		}
+	}
+
+	fn set_tile(&self, screenblock: usize, row: usize, col: usize, entry: TileEntry) {
+		TextScreenblockAddress::new(screenblock).row_col(row, col).write(
+			TextEntry::new()
+				.with_tile(entry.tile)
+				.with_palbank(entry.palbank)
+				.with_hflip(entry.hflip)
+				.with_vflip(entry.vflip),
+		);
+	}
+
+	fn set_scroll(&self, background: usize, x: u16, y: u16) {
+		let [horizontal, vertical] = [
+			[mmio::BG0HOFS, mmio::BG0VOFS],
+			[mmio::BG1HOFS, mmio::BG1VOFS],
+			[mmio::BG2HOFS, mmio::BG2VOFS],
+			[mmio::BG3HOFS, mmio::BG3VOFS],
+		][background];
+		horizontal.write(x);
+		vertical.write(y);
+	}
+
+	fn set_sprite(&self, index: usize, sprite: Option<Sprite>) {
+		let Some(sprite) = sprite else {
+			mmio::OBJ_ATTR0
+				.index(index)
+				.write(ObjAttr0::new().with_style(ObjDisplayStyle::NotDisplayed));
+			return;
+		};
+		let size = match sprite.size {
+			SpriteSize::S8x8 => 0,
+			SpriteSize::S16x16 => 1,
+			SpriteSize::S32x32 => 2,
+			SpriteSize::S64x64 => 3,
+		};
+		mmio::OBJ_ATTR0.index(index).write(ObjAttr0::new().with_y(sprite.y as u16));
+		mmio::OBJ_ATTR1.index(index).write(
+			ObjAttr1::new()
+				.with_x(sprite.x as u16)
+				.with_hflip(sprite.hflip)
+				.with_vflip(sprite.vflip)
+				.with_size(size),
+		);
+		mmio::OBJ_ATTR2.index(index).write(
+			ObjAttr2::new()
+				.with_tile_id(sprite.tile_id)
+				.with_palbank(sprite.palbank),
+		);
+	}
+
+	fn set_forced_blank(&self, blank: bool) {
+		mmio::DISPCNT.apply(|display| *display = display.with_forced_blank(blank));
+	}
+}
+
+/// The keypad.
+pub struct Keypad;
+
+impl KeySource for Keypad {
+	fn read(&mut self) -> Keys {
+		let keys: KeyInput = mmio::KEYINPUT.read();
+		let bits = [
+			keys.a(),
+			keys.b(),
+			keys.select(),
+			keys.start(),
+			keys.right(),
+			keys.left(),
+			keys.up(),
+			keys.down(),
+			keys.r(),
+			keys.l(),
+		]
+		.iter()
+		.enumerate()
+		.fold(0, |bits, (i, pressed)| bits | (*pressed as u16) << i);
+		Keys(bits)
+	}
+}
+
+/// The cartridge's save memory.
+pub struct Sram;
+
+impl Storage for Sram {
+	fn read(&self, offset: usize, buffer: &mut [u8]) {
+		for (i, byte) in buffer.iter_mut().enumerate() {
+			*byte = SRAM.index(offset + i).read();
+		}
+	}
+
+	fn write(&mut self, offset: usize, data: &[u8]) {
+		for (i, byte) in data.iter().enumerate() {
+			SRAM.index(offset + i).write(*byte);
+		}
+	}
+
+	fn size(&self) -> usize {
+		SRAM_SIZE
+	}
+}
+
@@ -0,0 +1,168 @@
+use crate::combat::{Weapon, WeaponKind};
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum ItemKind {
+	Weapon(WeaponKind),
+	/// Heals an ally within range.
+	Staff,
+	/// Heals the unit using it.
+	Consumable,
+}
+
+pub struct ItemData {
+	pub name: &'static str,
+	pub kind: ItemKind,
+	/// Number of times the item can be used before it breaks.
+	pub uses: u8,
+	pub might: u8,
+	pub hit: u8,
+	pub crit: u8,
+	pub weight: u8,
+	pub min_range: u8,
+	pub max_range: u8,
+	/// HP restored by staves and consumables.
+	pub heal: u8,
+}
+
+impl ItemData {
+	/// Returns the item's combat properties, if it is a weapon.
+	pub fn weapon(&self) -> Option<Weapon> {
+		let ItemKind::Weapon(kind) = self.kind else {
+			return None;
+		};
+		Some(Weapon {
+			kind,
+			might: self.might,
+			hit: self.hit,
+			crit: self.crit,
+			weight: self.weight,
+			min_range: self.min_range,
+			max_range: self.max_range,
+			magical: matches!(kind, WeaponKind::Anima | WeaponKind::Light | WeaponKind::Dark),
+		})
+	}
+}
+
+// Compiled from `assets/items.toml`: defines `ItemId` (with `ItemId::ALL`), `ITEM_COUNT` and `ITEMS`.
+include!(concat!(env!("OUT_DIR"), "/assets/items.rs"));
+
+impl ItemId {
+	pub fn data(self) -> &'static ItemData {
+		&ITEMS[self as usize]
+	}
+}
+
+/// A single item in a unit's inventory, along with its remaining uses.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Item {
+	pub id: ItemId,
+	pub uses: u8,
+}
+
+impl Item {
+	pub fn new(id: ItemId) -> Self {
+		Self {
+			id,
+			uses: id.data().uses,
+		}
+	}
+
+	pub fn data(&self) -> &'static ItemData {
+		self.id.data()
+	}
+}
+
+pub const INVENTORY_SIZE: usize = 5;
+
+/// A unit's items. The first slot holds the equipped weapon, if the unit has one.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Inventory {
+	slots: [Option<Item>; INVENTORY_SIZE],
+}
+
+impl Default for Inventory {
+	fn default() -> Self {
+		Self::new()
+	}
+}
+
+impl Inventory {
+	pub fn new() -> Self {
+		Self {
+			slots: [None; INVENTORY_SIZE],
+		}
+	}
+
+	/// Creates an inventory of fresh items. Any beyond INVENTORY_SIZE are ignored.
+	pub fn from_ids(ids: &[ItemId]) -> Self {
+		let mut inventory = Self::new();
+		for (slot, id) in inventory.slots.iter_mut().zip(ids) {
+			*slot = Some(Item::new(*id));
+		}
+		inventory
+	}
+
+	pub fn get(&self, slot: usize) -> Option<&Item> {
+		self.slots.get(slot)?.as_ref()
+	}
+
+	/// Iterates over each item along with its slot.
+	pub fn iter(&self) -> impl Iterator<Item = (usize, &Item)> + '_ {
+		self.slots
+			.iter()
+			.enumerate()
+			.filter_map(|(i, slot)| Some((i, slot.as_ref()?)))
+	}
+
+	pub fn len(&self) -> usize {
+		self.iter().count()
+	}
+
+	pub fn is_empty(&self) -> bool {
+		self.slots.iter().all(|slot| slot.is_none())
+	}
+
+	pub fn is_full(&self) -> bool {
+		self.slots.iter().all(|slot| slot.is_some())
+	}
+
+	/// Adds an item to the end of the inventory, or hands it back if there is no room.
+	pub fn push(&mut self, item: Item) -> Result<(), Item> {
+		match self.slots.iter_mut().find(|slot| slot.is_none()) {
+			Some(slot) => {
+				*slot = Some(item);
+				Ok(())
+			}
+			None => Err(item),
+		}
+	}
+
+	/// Removes an item, shifting the ones after it forward.
+	pub fn remove(&mut self, slot: usize) -> Option<Item> {
+		let item = self.slots.get_mut(slot)?.take()?;
+		self.slots[slot..].rotate_left(1);
+		Some(item)
+	}
+
+	/// Moves an item to the first slot, equipping it.
+	pub fn equip(&mut self, slot: usize) {
+		if slot < INVENTORY_SIZE && self.slots[slot].is_some() {
+			self.slots[..=slot].rotate_right(1);
+		}
+	}
+
+	/// Spends one use of an item, removing it if it breaks.
+	/// Returns true if the item broke.
+	pub fn spend(&mut self, slot: usize) -> bool {
+		let Some(item) = self.slots.get_mut(slot).and_then(|slot| slot.as_mut()) else {
+			return false;
+		};
+		item.uses = item.uses.saturating_sub(1);
+		if item.uses == 0 {
+			self.remove(slot);
+			true
+		} else {
+			false
+		}
+	}
+}
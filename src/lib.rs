@@ -0,0 +1,34 @@
+#![cfg_attr(not(test), no_std)]
+
+//! Everything that makes up the game, apart from the hardware it runs on.
+//!
+//! Drawing goes through [`video::Video`] and input through [`console::KeySource`],
+//! so the game can be driven from the GBA binary in `main.rs` or from host-side tests.
+
+pub mod ai;
+pub mod assets;
+pub mod camera;
+pub mod campaign;
+pub mod class;
+pub mod combat;
+pub mod console;
+pub mod experience;
+pub mod game;
+pub mod item;
+pub mod menu;
+pub mod movement;
+pub mod objective;
+pub mod ppu;
+pub mod replay;
+pub mod rng;
+pub mod save;
+pub mod scene;
+pub mod stats;
+pub mod terrain;
+pub mod text;
+pub mod title;
+pub mod tools;
+pub mod transform;
+pub mod turn;
+pub mod unit;
+pub mod video;
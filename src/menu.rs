@@ -0,0 +1,79 @@
+use crate::console::Input;
+use crate::text::{TextLayer, TextWriter};
+use crate::transform::{AxisY, Bounds, Edge, Vector2D};
+use crate::video::Video;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum MenuEvent {
+	/// The player chose the option at this index.
+	Confirm(usize),
+	Cancel,
+}
+
+/// A vertical list of options, navigated with up and down, chosen with A and dismissed with B.
+/// Holding up or down scrolls through the options.
+#[derive(Clone, Copy, Debug)]
+pub struct Menu {
+	pub selected: usize,
+	pub len: usize,
+	/// Whether moving past the first or last option wraps around to the other end.
+	pub edge: Edge,
+}
+
+impl Menu {
+	pub fn new(len: usize) -> Self {
+		Self {
+			selected: 0,
+			len,
+			edge: Edge::Clamp,
+		}
+	}
+
+	pub fn with_edge(self, edge: Edge) -> Self {
+		Self { edge, ..self }
+	}
+
+	/// Moves the selection and reports whether an option was chosen or the menu was dismissed.
+	pub fn update(&mut self, input: &Input) -> Option<MenuEvent> {
+		let offset = match input.get_repeated_y() {
+			Some(AxisY::Up) => -1,
+			Some(AxisY::Down) => 1,
+			None => 0,
+		};
+		let bounds = Bounds::new(1, self.len as u16).with_edge(self.edge);
+		self.selected = bounds
+			.step(Vector2D { x: 0, y: self.selected as i16 }, Vector2D { x: 0, y: offset })
+			.y as usize;
+
+		if input.new.a() && self.selected < self.len {
+			Some(MenuEvent::Confirm(self.selected))
+		} else if input.new.b() {
+			Some(MenuEvent::Cancel)
+		} else {
+			None
+		}
+	}
+
+	/// Changes the number of options, keeping the selection within bounds.
+	pub fn resize(&mut self, len: usize) {
+		self.len = len;
+		self.selected = self.selected.min(len.saturating_sub(1));
+	}
+
+	/// Draws the menu as a window with one option per row and a '>' beside the selection.
+	/// `label` writes the text of each option.
+	pub fn draw<V: Video>(
+		&self,
+		text: &TextLayer<V>,
+		x: usize,
+		y: usize,
+		width: usize,
+		mut label: impl FnMut(usize, &mut TextWriter<V>),
+	) {
+		text.fill(x, y, width, self.len + 2);
+		for i in 0..self.len {
+			text.put(x + 1, y + 1 + i, if i == self.selected { '>' } else { ' ' });
+			label(i, &mut text.at(x + 2, y + 1 + i));
+		}
+	}
+}
@@ -0,0 +1,257 @@
+use crate::transform::{Direction4, Vector2D};
+
+// Maps are limited to 32x32 metatiles so that a full bitmap is only 128 bytes.
+// The scratch space used by `flood_fill` is larger: about 5.4KB of stack, mostly its queue.
+pub const MAX_MAP_WIDTH: u16 = 32;
+pub const MAX_MAP_HEIGHT: u16 = 32;
+pub const MAX_MAP_TILES: usize = (MAX_MAP_WIDTH * MAX_MAP_HEIGHT) as usize;
+
+/// A set of map positions, stored as one bit per tile.
+#[derive(Clone)]
+pub struct MapBitmap {
+	width: u16,
+	height: u16,
+	bits: [u32; MAX_MAP_TILES / 32],
+}
+
+impl MapBitmap {
+	pub fn new(width: u16, height: u16) -> Self {
+		assert!(width <= MAX_MAP_WIDTH && height <= MAX_MAP_HEIGHT);
+		Self {
+			width,
+			height,
+			bits: [0; MAX_MAP_TILES / 32],
+		}
+	}
+
+	pub fn width(&self) -> u16 {
+		self.width
+	}
+
+	pub fn height(&self) -> u16 {
+		self.height
+	}
+
+	/// Converts a position into a bit index, or None if it lies outside of the map.
+	fn index(&self, position: Vector2D<i16>) -> Option<usize> {
+		if position.x < 0
+			|| position.y < 0
+			|| position.x >= self.width as i16
+			|| position.y >= self.height as i16
+		{
+			None
+		} else {
+			Some(position.x as usize + position.y as usize * self.width as usize)
+		}
+	}
+
+	/// Returns true if the position is a member of the set.
+	/// Positions outside of the map are never members.
+	pub fn contains(&self, position: Vector2D<i16>) -> bool {
+		match self.index(position) {
+			Some(i) => self.bits[i / 32] & (1 << (i % 32)) != 0,
+			None => false,
+		}
+	}
+
+	/// Adds a position to the set. Positions outside of the map are ignored.
+	pub fn insert(&mut self, position: Vector2D<i16>) {
+		if let Some(i) = self.index(position) {
+			self.bits[i / 32] |= 1 << (i % 32);
+		}
+	}
+
+	/// Removes a position from the set.
+	pub fn remove(&mut self, position: Vector2D<i16>) {
+		if let Some(i) = self.index(position) {
+			self.bits[i / 32] &= !(1 << (i % 32));
+		}
+	}
+
+	pub fn clear(&mut self) {
+		self.bits = [0; MAX_MAP_TILES / 32];
+	}
+
+	pub fn is_empty(&self) -> bool {
+		self.bits.iter().all(|word| *word == 0)
+	}
+
+	/// Iterates over every position in the set, in row-major order.
+	pub fn iter(&self) -> impl Iterator<Item = Vector2D<i16>> + '_ {
+		(0..self.height as i16)
+			.flat_map(move |y| (0..self.width as i16).map(move |x| Vector2D { x, y }))
+			.filter(move |position| self.contains(*position))
+	}
+}
+
+/// Computes every tile a unit at `origin` can reach with `movement` points.
+///
+/// `cost` returns the number of movement points needed to enter a tile,
+/// or None if the tile cannot be entered at all.
+/// The origin is always reachable, regardless of its cost.
+pub fn flood_fill(
+	origin: Vector2D<i16>,
+	movement: u8,
+	width: u16,
+	height: u16,
+	mut cost: impl FnMut(Vector2D<i16>) -> Option<u8>,
+) -> MapBitmap {
+	let mut reachable = MapBitmap::new(width, height);
+	let Some(origin_index) = reachable.index(origin) else {
+		return reachable;
+	};
+
+	// Remaining movement points upon entering each tile, offset by one so that 0 means unvisited.
+	let mut remaining = [0u8; MAX_MAP_TILES];
+	// Tiles waiting to be expanded. A tile is only ever queued once at a time,
+	// so the ring buffer can never hold more than MAX_MAP_TILES entries.
+	let mut queue = [Vector2D { x: 0, y: 0 }; MAX_MAP_TILES];
+	let mut queued = MapBitmap::new(width, height);
+	let mut head = 0;
+	let mut length = 1;
+
+	remaining[origin_index] = movement.saturating_add(1);
+	queue[0] = origin;
+	queued.insert(origin);
+	reachable.insert(origin);
+
+	while length > 0 {
+		let position = queue[head];
+		head = (head + 1) % MAX_MAP_TILES;
+		length -= 1;
+		queued.remove(position);
+
+		let points = remaining[reachable.index(position).unwrap()] - 1;
+
+		for direction in Direction4::ALL {
+			let next = position + direction.to_vector();
+			let Some(next_index) = reachable.index(next) else {
+				continue;
+			};
+			let Some(tile_cost) = cost(next) else {
+				continue;
+			};
+			if tile_cost > points {
+				continue;
+			}
+			let left = points - tile_cost + 1;
+			if left <= remaining[next_index] {
+				continue;
+			}
+
+			remaining[next_index] = left;
+			reachable.insert(next);
+			if !queued.contains(next) {
+				queued.insert(next);
+				queue[(head + length) % MAX_MAP_TILES] = next;
+				length += 1;
+			}
+		}
+	}
+
+	reachable
+}
+
+/// Computes every tile that can be attacked from somewhere in `reachable`,
+/// given a weapon range of `min_range..=max_range` tiles.
+/// Tiles that are themselves reachable are excluded.
+pub fn attack_range(reachable: &MapBitmap, min_range: u8, max_range: u8) -> MapBitmap {
+	let mut attackable = MapBitmap::new(reachable.width(), reachable.height());
+	let max_range = max_range as i16;
+	for origin in reachable.iter() {
+		for y in -max_range..=max_range {
+			let span = max_range - y.abs();
+			for x in -span..=span {
+				if ((x.abs() + y.abs()) as u8) < min_range {
+					continue;
+				}
+				let target = origin + Vector2D { x, y };
+				if !reachable.contains(target) {
+					attackable.insert(target);
+				}
+			}
+		}
+	}
+	attackable
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn at(x: i16, y: i16) -> Vector2D<i16> {
+		Vector2D { x, y }
+	}
+
+	fn plains(_: Vector2D<i16>) -> Option<u8> {
+		Some(1)
+	}
+
+	#[test]
+	fn movement_runs_out() {
+		let reachable = flood_fill(at(2, 2), 2, 5, 5, plains);
+		// A diamond with a radius of two tiles.
+		assert_eq!(reachable.iter().count(), 13);
+		assert!(reachable.contains(at(2, 0)));
+		assert!(reachable.contains(at(3, 3)));
+		assert!(!reachable.contains(at(0, 0)));
+		assert!(!reachable.contains(at(4, 3)));
+	}
+
+	#[test]
+	fn expensive_tiles_use_up_movement() {
+		let forest_column = |position: Vector2D<i16>| Some(if position.x == 3 { 2 } else { 1 });
+		let reachable = flood_fill(at(2, 2), 2, 5, 5, forest_column);
+		assert!(reachable.contains(at(3, 2)));
+		assert!(!reachable.contains(at(4, 2)));
+		assert!(!reachable.contains(at(3, 1)));
+	}
+
+	#[test]
+	fn impassable_tiles_block_movement() {
+		let wall = |position: Vector2D<i16>| if position.x == 3 { None } else { Some(1) };
+		let reachable = flood_fill(at(2, 2), 10, 5, 5, wall);
+		assert!((0..5).all(|y| !reachable.contains(at(3, y)) && !reachable.contains(at(4, y))));
+		assert_eq!(reachable.iter().count(), 15);
+	}
+
+	#[test]
+	fn the_origin_is_reachable_whatever_it_costs() {
+		let reachable = flood_fill(at(1, 1), 0, 3, 3, |_| None);
+		assert_eq!(reachable.iter().collect::<Vec<_>>(), [at(1, 1)]);
+	}
+
+	#[test]
+	fn movement_stops_at_the_edges() {
+		let reachable = flood_fill(at(0, 0), 1, 3, 3, plains);
+		assert_eq!(reachable.iter().collect::<Vec<_>>(), [at(0, 0), at(1, 0), at(0, 1)]);
+		assert!(!reachable.contains(at(-1, 0)));
+
+		let everywhere = flood_fill(at(0, 0), 10, 3, 3, plains);
+		assert_eq!(everywhere.iter().count(), 9);
+
+		assert!(flood_fill(at(3, 0), 10, 3, 3, plains).is_empty());
+	}
+
+	#[test]
+	fn attack_range_is_a_ring() {
+		let mut reachable = MapBitmap::new(7, 7);
+		reachable.insert(at(3, 3));
+
+		let adjacent = attack_range(&reachable, 1, 1);
+		assert_eq!(adjacent.iter().collect::<Vec<_>>(), [at(3, 2), at(2, 3), at(4, 3), at(3, 4)]);
+
+		// Bows can't hit adjacent tiles.
+		let bow = attack_range(&reachable, 2, 2);
+		assert_eq!(bow.iter().count(), 8);
+		assert!(bow.contains(at(3, 1)) && bow.contains(at(4, 4)));
+		assert!(!bow.contains(at(3, 2)) && !bow.contains(at(3, 3)));
+	}
+
+	#[test]
+	fn attack_range_leaves_out_reachable_tiles_and_the_edges() {
+		let reachable = flood_fill(at(0, 0), 1, 4, 4, plains);
+		let attackable = attack_range(&reachable, 1, 1);
+		assert_eq!(attackable.iter().collect::<Vec<_>>(), [at(2, 0), at(1, 1), at(0, 2)]);
+	}
+}
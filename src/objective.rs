@@ -0,0 +1,184 @@
+use crate::transform::Vector2D;
+use crate::turn::TurnState;
+use crate::unit::{Faction, UnitList};
+
+/// What the player must do to clear a chapter.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Objective {
+	/// Defeat every enemy unit.
+	Rout,
+	/// Defeat every enemy boss.
+	DefeatBoss,
+	/// Move the lord (or any player unit, if there is no lord) onto this tile.
+	Seize(Vector2D<i16>),
+	/// Hold out until this turn is over.
+	Survive(u16),
+	/// Leave the map through this tile. Units which end their action on it escape.
+	/// The chapter is cleared once the lord escapes, or everyone has if there is no lord.
+	Escape(Vector2D<i16>),
+}
+
+/// Ways to lose a chapter. Losing every player unit is always a defeat.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Defeat<'a> {
+	/// Name of a player unit whose death ends the chapter.
+	pub lord: Option<&'a str>,
+	/// The chapter is lost once this turn is over.
+	pub turn_limit: Option<u16>,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum ChapterResult {
+	Victory,
+	Defeat,
+}
+
+impl Objective {
+	/// Returns true if the objective has been met.
+	/// `escaped` is the number of player units which have left the map, and whether the lord was among them.
+	pub fn is_complete(&self, units: &UnitList, turn: &TurnState, lord: Option<&str>, escaped: (u8, bool)) -> bool {
+		let mut foes = units
+			.iter()
+			.filter(|(_, unit)| Faction::Player.is_hostile_to(unit.faction));
+		match *self {
+			Objective::Rout => foes.next().is_none(),
+			Objective::DefeatBoss => !foes.any(|(_, unit)| unit.is_boss),
+			Objective::Seize(tile) => units.iter().any(|(_, unit)| {
+				unit.faction == Faction::Player
+					&& unit.position == tile
+					&& lord.is_none_or(|lord| unit.name == lord)
+			}),
+			Objective::Survive(turns) => turn.turn > turns,
+			Objective::Escape(_) => {
+				let (count, lord_escaped) = escaped;
+				lord_escaped
+					|| (count > 0 && !units.iter().any(|(_, unit)| unit.faction == Faction::Player))
+			}
+		}
+	}
+}
+
+impl<'a> Defeat<'a> {
+	/// Returns true if the chapter has been lost.
+	/// A lord who escaped the map is safe, not dead.
+	pub fn is_lost(&self, units: &UnitList, turn: &TurnState, lord_escaped: bool) -> bool {
+		let players = || units.iter().filter(|(_, unit)| unit.faction == Faction::Player);
+		let lord_died = self
+			.lord
+			.is_some_and(|lord| !lord_escaped && !players().any(|(_, unit)| unit.name == lord));
+		let out_of_time = self.turn_limit.is_some_and(|limit| turn.turn > limit);
+		lord_died || out_of_time || players().next().is_none()
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::ai::Behavior;
+	use crate::class::ClassId;
+	use crate::game::UnitData;
+	use crate::unit::Unit;
+
+	fn unit(name: &'static str, faction: Faction, x: u16, is_boss: bool) -> Unit<'static> {
+		Unit::new(&UnitData {
+			name,
+			class: ClassId::Fighter,
+			x,
+			y: 0,
+			is_boss,
+			level: 1,
+			faction,
+			items: &[],
+			behavior: Behavior::Aggressive,
+		})
+	}
+
+	/// A lord and a soldier at x = 0 and 1, a bandit at 2 and its boss at 3.
+	fn units() -> UnitList<'static> {
+		let mut units = UnitList::new();
+		units.push(unit("Lord", Faction::Player, 0, false)).unwrap();
+		units.push(unit("Soldier", Faction::Player, 1, false)).unwrap();
+		units.push(unit("Bandit", Faction::Enemy, 2, false)).unwrap();
+		units.push(unit("Boss", Faction::Enemy, 3, true)).unwrap();
+		units
+	}
+
+	fn turn(turn: u16) -> TurnState {
+		TurnState {
+			turn,
+			phase: Faction::Player,
+		}
+	}
+
+	#[test]
+	fn rout_needs_every_enemy_gone() {
+		let mut units = units();
+		units.remove(3);
+		assert!(!Objective::Rout.is_complete(&units, &turn(1), None, (0, false)));
+		units.remove(2);
+		assert!(Objective::Rout.is_complete(&units, &turn(1), None, (0, false)));
+	}
+
+	#[test]
+	fn defeat_boss_ignores_the_other_enemies() {
+		let mut units = units();
+		assert!(!Objective::DefeatBoss.is_complete(&units, &turn(1), None, (0, false)));
+		units.remove(3);
+		assert!(Objective::DefeatBoss.is_complete(&units, &turn(1), None, (0, false)));
+	}
+
+	#[test]
+	fn seize_needs_the_lord_on_the_tile() {
+		let units = units();
+		let soldier_tile = Objective::Seize(Vector2D { x: 1, y: 0 });
+		assert!(!soldier_tile.is_complete(&units, &turn(1), Some("Lord"), (0, false)));
+		assert!(Objective::Seize(Vector2D { x: 0, y: 0 }).is_complete(&units, &turn(1), Some("Lord"), (0, false)));
+		// Without a lord, anyone on the player's side will do, but not an enemy.
+		assert!(soldier_tile.is_complete(&units, &turn(1), None, (0, false)));
+		assert!(!Objective::Seize(Vector2D { x: 2, y: 0 }).is_complete(&units, &turn(1), None, (0, false)));
+	}
+
+	#[test]
+	fn survive_lasts_until_the_turn_is_over() {
+		let units = units();
+		assert!(!Objective::Survive(3).is_complete(&units, &turn(3), None, (0, false)));
+		assert!(Objective::Survive(3).is_complete(&units, &turn(4), None, (0, false)));
+	}
+
+	#[test]
+	fn escape_needs_the_lord_or_everyone_out() {
+		let mut units = units();
+		let escape = Objective::Escape(Vector2D { x: 5, y: 0 });
+		assert!(escape.is_complete(&units, &turn(1), Some("Lord"), (1, true)));
+		assert!(!escape.is_complete(&units, &turn(1), None, (1, false)));
+
+		units.remove(0);
+		units.remove(1);
+		assert!(escape.is_complete(&units, &turn(1), None, (2, false)));
+		// Losing everyone isn't an escape.
+		assert!(!escape.is_complete(&units, &turn(1), None, (0, false)));
+	}
+
+	#[test]
+	fn chapters_are_lost_with_the_lord_the_time_or_everyone() {
+		let mut units = units();
+		let defeat = Defeat {
+			lord: Some("Lord"),
+			turn_limit: Some(5),
+		};
+		assert!(!defeat.is_lost(&units, &turn(5), false));
+		assert!(defeat.is_lost(&units, &turn(6), false));
+
+		units.remove(0);
+		assert!(defeat.is_lost(&units, &turn(1), false));
+		// A lord who escaped isn't dead.
+		assert!(!defeat.is_lost(&units, &turn(1), true));
+
+		units.remove(1);
+		let none = Defeat {
+			lord: None,
+			turn_limit: None,
+		};
+		assert!(none.is_lost(&units, &turn(1), false));
+	}
+}
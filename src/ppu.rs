@@ -0,0 +1,387 @@
+use crate::camera::{SCREEN_HEIGHT, SCREEN_WIDTH};
+use crate::video::{Sprite, TileEntry, Video};
+use core::cell::RefCell;
+
+const WIDTH: usize = SCREEN_WIDTH as usize;
+const HEIGHT: usize = SCREEN_HEIGHT as usize;
+
+/// Size of background VRAM: four charblocks, which the screenblocks overlap.
+const BG_VRAM_SIZE: usize = 0x10000;
+const OBJ_VRAM_SIZE: usize = 0x8000;
+const CHARBLOCK_SIZE: usize = 0x4000;
+const SCREENBLOCK_SIZE: usize = 0x800;
+const TILE_SIZE: usize = 32;
+
+/// How a background layer is set up. This is what BGxCNT holds on hardware.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Background {
+	pub enabled: bool,
+	/// Layers with a lower priority are drawn above those with a higher one.
+	pub priority: u8,
+	pub charblock: usize,
+	pub screenblock: usize,
+}
+
+/// Mixes one layer over another. `top_weight` and `bottom_weight` are sixteenths, as in BLDALPHA.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Blend {
+	pub top: usize,
+	pub bottom: usize,
+	pub top_weight: u16,
+	pub bottom_weight: u16,
+}
+
+/// What the software PPU's memory holds. Mirrors VRAM, palette RAM, OAM and DISPCNT's forced blank.
+struct Memory {
+	bg_vram: [u8; BG_VRAM_SIZE],
+	obj_vram: [u8; OBJ_VRAM_SIZE],
+	bg_palette: [u16; 256],
+	obj_palette: [u16; 256],
+	scroll: [(u16, u16); 4],
+	sprites: [Option<Sprite>; 128],
+	forced_blank: bool,
+}
+
+/// A software copy of the GBA's PPU, for seeing what the game draws without an emulator.
+///
+/// It only supports what the game uses: text mode backgrounds, 4bpp tiles, square sprites in 1D mapping,
+/// and a single alpha blend. Hand a reference to it to anything which wants a `Video`,
+/// then call `render` to get the frame.
+pub struct SoftwarePpu {
+	memory: RefCell<Memory>,
+	pub backgrounds: [Background; 4],
+	pub blend: Option<Blend>,
+}
+
+impl Default for SoftwarePpu {
+	fn default() -> Self {
+		Self::new()
+	}
+}
+
+impl SoftwarePpu {
+	/// Creates a PPU with the same background setup as the ROM: the map on BG0,
+	/// the range overlay blended over it on BG1, and text on BG2.
+	pub fn new() -> Self {
+		let background = |priority, screenblock| Background {
+			enabled: true,
+			priority,
+			charblock: 0,
+			screenblock,
+		};
+		Self {
+			memory: RefCell::new(Memory {
+				bg_vram: [0; BG_VRAM_SIZE],
+				obj_vram: [0; OBJ_VRAM_SIZE],
+				bg_palette: [0; 256],
+				obj_palette: [0; 256],
+				scroll: [(0, 0); 4],
+				sprites: [None; 128],
+				forced_blank: false,
+			}),
+			backgrounds: [
+				background(2, 8),
+				background(1, 9),
+				background(0, 10),
+				Background {
+					enabled: false,
+					..background(3, 11)
+				},
+			],
+			blend: Some(Blend {
+				top: 1,
+				bottom: 0,
+				top_weight: 8,
+				bottom_weight: 8,
+			}),
+		}
+	}
+
+	/// Draws the current contents of VRAM and OAM.
+	pub fn render(&self) -> Frame {
+		let memory = self.memory.borrow();
+		let mut frame = Frame {
+			pixels: [[0x7FFF; WIDTH]; HEIGHT],
+		};
+		if memory.forced_blank {
+			return frame;
+		}
+
+		// Lower OAM entries are drawn above higher ones, so draw from the back.
+		let mut objects: [[Option<u16>; WIDTH]; HEIGHT] = [[None; WIDTH]; HEIGHT];
+		for sprite in memory.sprites.iter().rev().flatten() {
+			memory.draw_sprite(sprite, &mut objects);
+		}
+
+		for (y, row) in frame.pixels.iter_mut().enumerate() {
+			for (x, pixel) in row.iter_mut().enumerate() {
+				*pixel = self.compose(&memory, objects[y][x], x, y);
+			}
+		}
+		frame
+	}
+
+	/// Finds the topmost colors at a pixel, and blends them if the blend asks for it.
+	fn compose(&self, memory: &Memory, object: Option<u16>, x: usize, y: usize) -> u16 {
+		#[derive(Clone, Copy, PartialEq)]
+		enum Layer {
+			Object,
+			Background(usize),
+			Backdrop,
+		}
+
+		let mut found = [(Layer::Backdrop, memory.bg_palette[0]); 2];
+		let mut count = 0;
+		// Sprites are all priority 0, which puts them above every background.
+		let layers = object.map(|color| (Layer::Object, color)).into_iter().chain(
+			(0..4u8).flat_map(move |priority| {
+				(0..4).filter_map(move |i| {
+					let background = self.backgrounds[i];
+					(background.enabled && background.priority == priority)
+						.then(|| memory.background_pixel(background, memory.scroll[i], x, y))
+						.flatten()
+						.map(|color| (Layer::Background(i), color))
+				})
+			}),
+		);
+		for layer in layers {
+			found[count] = layer;
+			count += 1;
+			if count == found.len() {
+				break;
+			}
+		}
+
+		let [(top, top_color), (bottom, bottom_color)] = found;
+		match self.blend {
+			Some(blend)
+				if top == Layer::Background(blend.top) && bottom == Layer::Background(blend.bottom) =>
+			{
+				mix(top_color, bottom_color, blend.top_weight, blend.bottom_weight)
+			}
+			_ => top_color,
+		}
+	}
+}
+
+impl Memory {
+	/// Returns the color of a background at a screen pixel, or None if it is transparent there.
+	fn background_pixel(&self, background: Background, scroll: (u16, u16), x: usize, y: usize) -> Option<u16> {
+		// A screenblock is 256x256 pixels, and wraps around as it scrolls.
+		let map_x = (x + scroll.0 as usize) % 256;
+		let map_y = (y + scroll.1 as usize) % 256;
+		let address = background.screenblock * SCREENBLOCK_SIZE + (map_y / 8 * 32 + map_x / 8) * 2;
+		let entry = TileEntry::from_bits(u16::from_le_bytes([self.bg_vram[address], self.bg_vram[address + 1]]));
+
+		let tile = background.charblock * CHARBLOCK_SIZE + entry.tile as usize * TILE_SIZE;
+		let index = tile_pixel(&self.bg_vram, tile, map_x % 8, map_y % 8, entry.hflip, entry.vflip)?;
+		Some(self.bg_palette[entry.palbank as usize * 16 + index])
+	}
+
+	fn draw_sprite(&self, sprite: &Sprite, objects: &mut [[Option<u16>; WIDTH]; HEIGHT]) {
+		let size = sprite.size.pixels() as usize;
+		let tiles_per_row = size / 8;
+		for sprite_y in 0..size {
+			for sprite_x in 0..size {
+				let x = sprite.x as isize + sprite_x as isize;
+				let y = sprite.y as isize + sprite_y as isize;
+				if !(0..WIDTH as isize).contains(&x) || !(0..HEIGHT as isize).contains(&y) {
+					continue;
+				}
+				let source_x = if sprite.hflip { size - 1 - sprite_x } else { sprite_x };
+				let source_y = if sprite.vflip { size - 1 - sprite_y } else { sprite_y };
+				// 1D mapping: a sprite's tiles follow one another in VRAM, a row at a time.
+				let tile_id = sprite.tile_id as usize + source_y / 8 * tiles_per_row + source_x / 8;
+				let tile = tile_id * TILE_SIZE % OBJ_VRAM_SIZE;
+				if let Some(index) = tile_pixel(&self.obj_vram, tile, source_x % 8, source_y % 8, false, false) {
+					objects[y as usize][x as usize] = Some(self.obj_palette[sprite.palbank as usize * 16 + index]);
+				}
+			}
+		}
+	}
+}
+
+/// Reads a color index from a 4bpp tile. Index 0 is transparent.
+fn tile_pixel(vram: &[u8], tile: usize, x: usize, y: usize, hflip: bool, vflip: bool) -> Option<usize> {
+	let x = if hflip { 7 - x } else { x };
+	let y = if vflip { 7 - y } else { y };
+	let byte = vram[(tile + y * 4 + x / 2) % vram.len()];
+	let index = if x % 2 == 0 { byte & 0xF } else { byte >> 4 };
+	(index != 0).then_some(index as usize)
+}
+
+/// Blends two 15-bit colors, clamping each channel.
+fn mix(top: u16, bottom: u16, top_weight: u16, bottom_weight: u16) -> u16 {
+	let channel = |shift: u16| {
+		let top = (top >> shift) & 0x1F;
+		let bottom = (bottom >> shift) & 0x1F;
+		((top * top_weight + bottom * bottom_weight) / 16).min(0x1F) << shift
+	};
+	channel(0) | channel(5) | channel(10)
+}
+
+impl Video for &SoftwarePpu {
+	fn load_bg_tiles(&self, index: usize, data: &[u32]) {
+		let mut memory = self.memory.borrow_mut();
+		for (i, word) in data.iter().enumerate() {
+			let address = index * TILE_SIZE + i * 4;
+			memory.bg_vram[address..address + 4].copy_from_slice(&word.to_le_bytes());
+		}
+	}
+
+	fn load_obj_tiles(&self, index: usize, data: &[u32]) {
+		let mut memory = self.memory.borrow_mut();
+		for (i, word) in data.iter().enumerate() {
+			let address = index * TILE_SIZE + i * 4;
+			memory.obj_vram[address..address + 4].copy_from_slice(&word.to_le_bytes());
+		}
+	}
+
+	fn load_bg_colors(&self, index: usize, colors: &[u16]) {
+		self.memory.borrow_mut().bg_palette[index..index + colors.len()].copy_from_slice(colors);
+	}
+
+	fn load_obj_colors(&self, index: usize, colors: &[u16]) {
+		self.memory.borrow_mut().obj_palette[index..index + colors.len()].copy_from_slice(colors);
+	}
+
+	fn set_tile(&self, screenblock: usize, row: usize, col: usize, entry: TileEntry) {
+		let address = screenblock * SCREENBLOCK_SIZE + (row * 32 + col) * 2;
+		self.memory.borrow_mut().bg_vram[address..address + 2].copy_from_slice(&entry.to_bits().to_le_bytes());
+	}
+
+	fn set_scroll(&self, background: usize, x: u16, y: u16) {
+		// Only the low 9 bits of each scroll register exist.
+		self.memory.borrow_mut().scroll[background] = (x & 0x1FF, y & 0x1FF);
+	}
+
+	fn set_sprite(&self, index: usize, sprite: Option<Sprite>) {
+		self.memory.borrow_mut().sprites[index] = sprite;
+	}
+
+	fn set_forced_blank(&self, blank: bool) {
+		self.memory.borrow_mut().forced_blank = blank;
+	}
+}
+
+/// One screen's worth of 15-bit colors, as produced by `SoftwarePpu::render`.
+#[derive(Clone, PartialEq, Eq)]
+pub struct Frame {
+	pub pixels: [[u16; WIDTH]; HEIGHT],
+}
+
+impl Frame {
+	/// Returns a pixel's color as 8-bit red, green and blue.
+	pub fn rgb(&self, x: usize, y: usize) -> [u8; 3] {
+		let color = self.pixels[y][x];
+		let channel = |shift: u16| {
+			let value = ((color >> shift) & 0x1F) as u8;
+			value << 3 | value >> 2
+		};
+		[channel(0), channel(5), channel(10)]
+	}
+
+	/// Encodes the frame as an uncompressed PNG, passing the file to `out` a piece at a time.
+	pub fn write_png(&self, mut out: impl FnMut(&[u8])) {
+		// Each row begins with a filter type byte (0, none).
+		const ROW_SIZE: usize = 1 + WIDTH * 3;
+		const IMAGE_SIZE: usize = ROW_SIZE * HEIGHT;
+		// zlib wants "stored" deflate blocks to hold no more than 65535 bytes each.
+		const BLOCK_SIZE: usize = 0xFFFF;
+		const BLOCKS: usize = IMAGE_SIZE.div_ceil(BLOCK_SIZE);
+
+		out(b"\x89PNG\r\n\x1a\n");
+
+		let mut header = [0; 13];
+		header[0..4].copy_from_slice(&(WIDTH as u32).to_be_bytes());
+		header[4..8].copy_from_slice(&(HEIGHT as u32).to_be_bytes());
+		// 8 bits per channel, RGB, default compression, filtering and no interlacing.
+		header[8..13].copy_from_slice(&[8, 2, 0, 0, 0]);
+		let mut chunk = Chunk::new(&mut out, b"IHDR", header.len());
+		chunk.write(&header);
+		chunk.finish();
+
+		let mut chunk = Chunk::new(&mut out, b"IDAT", 2 + BLOCKS * 5 + IMAGE_SIZE + 4);
+		chunk.write(&[0x78, 0x01]);
+		let mut adler = Adler32::new();
+		let mut written = 0;
+		for y in 0..HEIGHT {
+			let mut row = [0; ROW_SIZE];
+			for x in 0..WIDTH {
+				row[1 + x * 3..1 + x * 3 + 3].copy_from_slice(&self.rgb(x, y));
+			}
+			adler.update(&row);
+
+			let mut row = &row[..];
+			while !row.is_empty() {
+				if written % BLOCK_SIZE == 0 {
+					let len = (IMAGE_SIZE - written).min(BLOCK_SIZE) as u16;
+					let last = written + len as usize == IMAGE_SIZE;
+					chunk.write(&[last as u8]);
+					chunk.write(&len.to_le_bytes());
+					chunk.write(&(!len).to_le_bytes());
+				}
+				let take = row.len().min(BLOCK_SIZE - written % BLOCK_SIZE);
+				chunk.write(&row[..take]);
+				written += take;
+				row = &row[take..];
+			}
+		}
+		chunk.write(&adler.finish().to_be_bytes());
+		chunk.finish();
+
+		let chunk = Chunk::new(&mut out, b"IEND", 0);
+		chunk.finish();
+	}
+}
+
+/// A PNG chunk being written. The length must be known up front; the CRC is calculated as it goes.
+struct Chunk<'o, O: FnMut(&[u8])> {
+	out: &'o mut O,
+	crc: u32,
+}
+
+impl<'o, O: FnMut(&[u8])> Chunk<'o, O> {
+	fn new(out: &'o mut O, kind: &[u8; 4], len: usize) -> Self {
+		out(&(len as u32).to_be_bytes());
+		let mut chunk = Self { out, crc: 0xFFFFFFFF };
+		chunk.write(kind);
+		chunk
+	}
+
+	fn write(&mut self, data: &[u8]) {
+		for byte in data {
+			self.crc ^= *byte as u32;
+			for _ in 0..8 {
+				self.crc = if self.crc & 1 != 0 { (self.crc >> 1) ^ 0xEDB88320 } else { self.crc >> 1 };
+			}
+		}
+		(self.out)(data);
+	}
+
+	fn finish(self) {
+		(self.out)(&(!self.crc).to_be_bytes());
+	}
+}
+
+struct Adler32 {
+	a: u32,
+	b: u32,
+}
+
+impl Adler32 {
+	fn new() -> Self {
+		Self { a: 1, b: 0 }
+	}
+
+	fn update(&mut self, data: &[u8]) {
+		for byte in data {
+			self.a = (self.a + *byte as u32) % 65521;
+			self.b = (self.b + self.a) % 65521;
+		}
+	}
+
+	fn finish(&self) -> u32 {
+		self.b << 16 | self.a
+	}
+}
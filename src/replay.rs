@@ -0,0 +1,211 @@
+use crate::console::{KeySource, Keys};
+use crate::rng::Rng;
+use crate::save::{self, Reader, SaveError, SaveKind, Storage, Writer, PAYLOAD_SIZE, SUSPEND_SLOT};
+use crate::unit::UnitList;
+
+// Replay format
+//
+// A replay is a header followed by runs of identical key states, one per u16 (little endian):
+//   bits 0-9: the keys held, in the KEYINPUT layout; bits 10-15: how many frames they were held, from 1 to 63.
+// Longer runs are split in two. The header holds everything else needed to repeat a chapter exactly:
+//   version (u8), chapter (u8), RNG state (Rng::STATE_SIZE bytes), Input's frame counter (u32),
+//   keys held (u16), then the roster the chapter began with, as written by `save::write_units`.
+// Recording restarts whenever a chapter begins, so a replay only ever covers the current chapter.
+
+/// Increase this whenever the format changes, so that old replays are rejected rather than misread.
+pub const REPLAY_VERSION: u8 = 2;
+const KEY_BITS: u16 = 0x3FF;
+const MAX_RUN: u16 = 0x3F;
+
+/// Replays have a slot of their own, after the suspend slot.
+pub const REPLAY_SLOT: usize = SUSPEND_SLOT + 1;
+
+/// The state a replay begins from: the introduction of `chapter`, with the roster stored alongside it.
+/// Apply it before the first frame is played back.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct ReplayHeader {
+	pub chapter: u8,
+	/// The chapter's RNG, before any input is mixed into it.
+	pub rng: Rng,
+	/// `Input::frame` at the start of the replay. Input timing feeds the RNG, so this must match too.
+	pub frame: u32,
+	/// `Input::held` at the start of the replay, so that a key still held from the chapter before
+	/// isn't mistaken for a new press.
+	pub held: Keys,
+}
+
+/// Passes keys through from another source, recording them into a buffer as they are read.
+/// Nothing is recorded until `restart` is called.
+pub struct Recorder<'b, S: KeySource> {
+	source: S,
+	buffer: &'b mut [u8],
+	len: usize,
+	keys: Keys,
+	frames: u16,
+	/// Set once `restart` has written a header.
+	recording: bool,
+	full: bool,
+}
+
+impl<'b, S: KeySource> Recorder<'b, S> {
+	pub fn new(source: S, buffer: &'b mut [u8]) -> Self {
+		Self {
+			source,
+			buffer,
+			len: 0,
+			keys: Keys::NONE,
+			frames: 0,
+			recording: false,
+			full: false,
+		}
+	}
+
+	/// Throws away the recording so far and starts a new one from `header`,
+	/// where the chapter begins with `roster`. Call this as each chapter's introduction is entered.
+	pub fn restart(&mut self, header: &ReplayHeader, roster: &UnitList) {
+		self.len = 0;
+		self.keys = Keys::NONE;
+		self.frames = 0;
+		self.recording = true;
+		self.full = false;
+
+		let mut writer = Writer::new();
+		writer.u8(REPLAY_VERSION);
+		writer.u8(header.chapter);
+		save::write_rng(&mut writer, &header.rng);
+		writer.u32(header.frame);
+		writer.u16(header.held.0);
+		save::write_units(&mut writer, roster);
+		match writer.finish() {
+			Ok(header) => self.push(header),
+			Err(_) => self.full = true,
+		}
+	}
+
+	/// Returns true if the buffer has filled up. Nothing more is recorded until the next restart.
+	pub fn is_full(&self) -> bool {
+		self.full
+	}
+
+	fn push(&mut self, data: &[u8]) {
+		if !self.recording || self.full {
+			return;
+		}
+		match self.buffer.get_mut(self.len..self.len + data.len()) {
+			Some(space) => {
+				space.copy_from_slice(data);
+				self.len += data.len();
+			}
+			None => self.full = true,
+		}
+	}
+
+	/// Writes out the run in progress.
+	fn flush(&mut self) {
+		if self.frames > 0 {
+			let run = self.keys.0 & KEY_BITS | self.frames << 10;
+			self.push(&run.to_le_bytes());
+			self.frames = 0;
+		}
+	}
+
+	/// Returns everything recorded so far. Recording may continue afterwards.
+	/// If the buffer filled up, this is only the start of the chapter, up to the point it filled.
+	/// Fails if recording hasn't started, or the header alone didn't fit.
+	pub fn finish(&mut self) -> Result<&[u8], SaveError> {
+		self.flush();
+		if !self.recording {
+			Err(SaveError::Empty)
+		} else if self.len == 0 {
+			Err(SaveError::TooLarge)
+		} else {
+			Ok(&self.buffer[..self.len])
+		}
+	}
+}
+
+impl<'b, S: KeySource> KeySource for Recorder<'b, S> {
+	fn read(&mut self) -> Keys {
+		let keys = self.source.read();
+		if keys != self.keys || self.frames == MAX_RUN {
+			self.flush();
+			self.keys = keys;
+		}
+		self.frames += 1;
+		keys
+	}
+}
+
+/// Feeds recorded keys back in, one frame at a time. Once the recording runs out, no keys are held.
+pub struct Playback<'b> {
+	runs: &'b [u8],
+	keys: Keys,
+	remaining: u16,
+}
+
+impl<'b> Playback<'b> {
+	/// Reads a replay's header and the chapter's roster, returning the header along with a source which plays back its keys.
+	/// The roster is read into `roster`, and every unit's name must be one of `names`.
+	pub fn new<'a>(replay: &'b [u8], names: &[&'a str], roster: &mut UnitList<'a>) -> Result<(ReplayHeader, Self), SaveError> {
+		let mut reader = Reader::new(replay);
+		let version = reader.u8()?;
+		if version != REPLAY_VERSION {
+			return Err(SaveError::Version(version as u16));
+		}
+		let header = ReplayHeader {
+			chapter: reader.u8()?,
+			rng: save::read_rng(&mut reader)?,
+			frame: reader.u32()?,
+			held: Keys(reader.u16()? & KEY_BITS),
+		};
+		save::read_units(&mut reader, names, roster)?;
+		let runs = reader.rest();
+		// Every run is two bytes long and lasts at least one frame.
+		if !runs.len().is_multiple_of(2) || runs.chunks(2).any(|run| run[1] >> 2 == 0) {
+			return Err(SaveError::Corrupt);
+		}
+		Ok((
+			header,
+			Self {
+				runs,
+				keys: Keys::NONE,
+				remaining: 0,
+			},
+		))
+	}
+
+	/// Returns true once every recorded frame has been played.
+	pub fn is_finished(&self) -> bool {
+		self.remaining == 0 && self.runs.is_empty()
+	}
+}
+
+impl<'b> KeySource for Playback<'b> {
+	fn read(&mut self) -> Keys {
+		if self.remaining == 0 {
+			let Some((run, rest)) = self.runs.split_first_chunk::<2>() else {
+				return Keys::NONE;
+			};
+			let run = u16::from_le_bytes(*run);
+			self.runs = rest;
+			self.keys = Keys(run & KEY_BITS);
+			self.remaining = run >> 10;
+		}
+		self.remaining -= 1;
+		self.keys
+	}
+}
+
+/// Keeps a replay in save memory, so that it can be sent along with a bug report.
+pub fn save_replay(storage: &mut (impl Storage + ?Sized), replay: &[u8]) -> Result<(), SaveError> {
+	save::write_slot(storage, REPLAY_SLOT, SaveKind::Replay, replay)
+}
+
+/// Loads a replay saved by `save_replay` into `buffer`.
+pub fn load_replay<'b>(
+	storage: &(impl Storage + ?Sized),
+	buffer: &'b mut [u8; PAYLOAD_SIZE],
+) -> Result<&'b [u8], SaveError> {
+	let mut reader = save::read_slot(storage, REPLAY_SLOT, SaveKind::Replay, buffer)?;
+	Ok(reader.rest())
+}
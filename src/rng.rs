@@ -0,0 +1,235 @@
+use crate::combat::Rolls;
+
+/// Determines how hit rates are rolled.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum HitMode {
+	/// A single roll; the displayed hit rate is the true hit rate.
+	Single,
+	/// The average of two rolls, which makes high hit rates more reliable and low ones less so.
+	TrueHit,
+}
+
+/// Determines how stats grow on level up.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum GrowthMode {
+	/// Each stat is rolled against its growth rate.
+	Random,
+	/// Growth rates accumulate, and a stat increases each time its total passes 100.
+	Fixed,
+}
+
+/// A xorshift random number generator.
+/// Its entire state can be saved with `to_bytes` and restored with `from_bytes`.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Rng {
+	state: u32,
+	pub hit_mode: HitMode,
+	pub growth_mode: GrowthMode,
+}
+
+impl Rng {
+	/// Size of the array returned by `to_bytes`.
+	pub const STATE_SIZE: usize = 6;
+
+	pub fn new(seed: u32) -> Self {
+		Self {
+			// Xorshift never leaves a state of 0, so it must be avoided.
+			state: if seed == 0 { 0x2545_F491 } else { seed },
+			hit_mode: HitMode::TrueHit,
+			growth_mode: GrowthMode::Random,
+		}
+	}
+
+	pub fn next_u32(&mut self) -> u32 {
+		let mut x = self.state;
+		x ^= x << 13;
+		x ^= x >> 17;
+		x ^= x << 5;
+		self.state = x;
+		x
+	}
+
+	/// Returns a number from 0 to `max` - 1.
+	pub fn below(&mut self, max: u16) -> u16 {
+		(((self.next_u32() >> 16) * max as u32) >> 16) as u16
+	}
+
+	/// Returns a number from 0 to 99.
+	pub fn percent(&mut self) -> u8 {
+		self.below(100) as u8
+	}
+
+	/// Returns true if a roll succeeds with a `chance`% probability, according to `hit_mode`.
+	pub fn roll_hit(&mut self, chance: u8) -> bool {
+		let roll = match self.hit_mode {
+			HitMode::Single => self.percent(),
+			HitMode::TrueHit => (self.percent() + self.percent()) / 2,
+		};
+		roll < chance
+	}
+
+	/// Returns how many points a stat with a `rate`% growth gains this level, according to `growth_mode`.
+	/// `progress` carries fixed growths from one level to the next and is unused by random growths.
+	pub fn roll_growth(&mut self, rate: u8, progress: &mut u8) -> u8 {
+		match self.growth_mode {
+			GrowthMode::Random => rate / 100 + (self.percent() < rate % 100) as u8,
+			GrowthMode::Fixed => {
+				let total = *progress as u16 + rate as u16;
+				*progress = (total % 100) as u8;
+				(total / 100) as u8
+			}
+		}
+	}
+
+	/// Stirs outside entropy, such as input timing, into the state.
+	pub fn mix(&mut self, entropy: u32) {
+		self.state ^= entropy.wrapping_mul(0x9E37_79B9);
+		if self.state == 0 {
+			self.state = 0x2545_F491;
+		}
+		self.next_u32();
+	}
+
+	pub fn to_bytes(&self) -> [u8; Self::STATE_SIZE] {
+		let state = self.state.to_le_bytes();
+		[
+			state[0],
+			state[1],
+			state[2],
+			state[3],
+			self.hit_mode as u8,
+			self.growth_mode as u8,
+		]
+	}
+
+	/// Restores a state saved by `to_bytes`, or returns None if it is invalid.
+	pub fn from_bytes(bytes: [u8; Self::STATE_SIZE]) -> Option<Self> {
+		let state = u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]);
+		if state == 0 {
+			return None;
+		}
+		Some(Self {
+			state,
+			hit_mode: match bytes[4] {
+				0 => HitMode::Single,
+				1 => HitMode::TrueHit,
+				_ => return None,
+			},
+			growth_mode: match bytes[5] {
+				0 => GrowthMode::Random,
+				1 => GrowthMode::Fixed,
+				_ => return None,
+			},
+		})
+	}
+}
+
+impl Rolls for Rng {
+	fn hit(&mut self, chance: u8) -> bool {
+		self.roll_hit(chance)
+	}
+
+	/// Critical hits are always a single roll, regardless of `hit_mode`.
+	fn crit(&mut self, chance: u8) -> bool {
+		self.percent() < chance
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	/// Counts how many of `rolls` hits land at `chance`%.
+	fn hits(rng: &mut Rng, chance: u8, rolls: u32) -> u32 {
+		(0..rolls).filter(|_| rng.roll_hit(chance)).count() as u32
+	}
+
+	#[test]
+	fn rolls_stay_in_range() {
+		let mut rng = Rng::new(7);
+		assert!((0..1000).all(|_| rng.below(3) < 3));
+		assert!((0..1000).all(|_| rng.percent() < 100));
+	}
+
+	#[test]
+	fn hit_modes_agree_on_certainties() {
+		for hit_mode in [HitMode::Single, HitMode::TrueHit] {
+			let mut rng = Rng { hit_mode, ..Rng::new(1) };
+			assert_eq!(hits(&mut rng, 0, 1000), 0);
+			assert_eq!(hits(&mut rng, 100, 1000), 1000);
+		}
+	}
+
+	#[test]
+	fn true_hit_makes_high_rates_more_reliable() {
+		let mut single = Rng {
+			hit_mode: HitMode::Single,
+			..Rng::new(1)
+		};
+		let mut true_hit = Rng::new(1);
+
+		// A single roll at 80% lands about 8000 times in 10000; two averaged rolls land about 9200 times.
+		let single_hits = hits(&mut single, 80, 10_000);
+		let true_hits = hits(&mut true_hit, 80, 10_000);
+		assert!((7700..8300).contains(&single_hits), "{single_hits}");
+		assert!((8900..9500).contains(&true_hits), "{true_hits}");
+		// And low rates less reliable.
+		assert!(hits(&mut true_hit, 20, 10_000) < hits(&mut single, 20, 10_000));
+	}
+
+	#[test]
+	fn random_growths_above_100_are_guaranteed() {
+		let mut rng = Rng::new(3);
+		let mut progress = 0;
+		for _ in 0..100 {
+			assert_eq!(rng.roll_growth(0, &mut progress), 0);
+			assert_eq!(rng.roll_growth(100, &mut progress), 1);
+			assert!((1..=2).contains(&rng.roll_growth(150, &mut progress)));
+		}
+		assert_eq!(progress, 0);
+	}
+
+	#[test]
+	fn fixed_growths_carry_their_progress() {
+		let mut rng = Rng {
+			growth_mode: GrowthMode::Fixed,
+			..Rng::new(3)
+		};
+		let mut progress = 0;
+		let gains: Vec<u8> = (0..5).map(|_| rng.roll_growth(40, &mut progress)).collect();
+		assert_eq!(gains, [0, 0, 1, 0, 1]);
+		assert_eq!(progress, 0);
+
+		let gains: Vec<u8> = (0..4).map(|_| rng.roll_growth(150, &mut progress)).collect();
+		assert_eq!(gains, [1, 2, 1, 2]);
+	}
+
+	#[test]
+	fn saved_states_round_trip() {
+		let mut rng = Rng {
+			growth_mode: GrowthMode::Fixed,
+			..Rng::new(99)
+		};
+		rng.next_u32();
+		let mut restored = Rng::from_bytes(rng.to_bytes()).unwrap();
+		assert_eq!(restored, rng);
+		assert_eq!(restored.next_u32(), rng.next_u32());
+	}
+
+	#[test]
+	fn invalid_states_are_rejected() {
+		let valid = Rng::new(99).to_bytes();
+		assert!(Rng::from_bytes(valid).is_some());
+
+		// Xorshift would be stuck on a state of 0.
+		assert_eq!(Rng::from_bytes([0, 0, 0, 0, valid[4], valid[5]]), None);
+
+		let mut hit_mode = valid;
+		hit_mode[4] = 2;
+		assert_eq!(Rng::from_bytes(hit_mode), None);
+
+		let mut growth_mode = valid;
+		growth_mode[5] = 0xFF;
+		assert_eq!(Rng::from_bytes(growth_mode), None);
+	}
+}
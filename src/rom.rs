@@ -0,0 +1,243 @@
+use core::fmt::Write;
+use crate::hardware::{println, wait_vblank, Gba, Keypad, Sram};
+use furry_emblem::campaign::{ChapterIntro, CHAPTERS, NAMES};
+use furry_emblem::console::{Input, KeySource, Keys, Oam};
+use furry_emblem::replay::{self, Playback, Recorder, ReplayHeader};
+use furry_emblem::save::PAYLOAD_SIZE;
+use furry_emblem::scene::{SceneKind, SceneStack};
+use furry_emblem::title;
+use furry_emblem::unit::UnitList;
+use gba::interrupts::IrqBits;
+use gba::mgba::MgbaBufferedLogger;
+use gba::mgba::MgbaMessageLevel;
+use gba::mmio;
+use gba::video::BackgroundControl;
+use gba::video::BlendControl;
+use gba::video::Color;
+use gba::video::ColorEffectMode;
+use gba::video::DisplayControl;
+use gba::video::DisplayStatus;
+use gba::video::VideoMode::_0 as VideoMode0;
+
+/// Holds the session's replay. It is far too large for the stack, which lives in IWRAM.
+#[link_section = ".ewram"]
+static mut REPLAY_BUFFER: [u8; PAYLOAD_SIZE] = [0; PAYLOAD_SIZE];
+
+/// Holds every scene and the roster between chapters. A chapter alone takes several kilobytes,
+/// which is better kept out of the small stack in IWRAM.
+#[link_section = ".ewram"]
+static mut SCENES: SceneStack<'static, Gba> = SceneStack::empty(Gba);
+
+/// Save memory, lent to the scenes for as long as the game runs.
+static mut SAVE_MEMORY: Sram = Sram;
+
+/// Emulators and flash carts work out what kind of save memory a game has
+/// by searching its ROM for an ID like this one, which must be word aligned.
+#[repr(C, align(4))]
+struct SaveId([u8; 12]);
+
+#[used]
+static SAVE_ID: SaveId = SaveId(*b"SRAM_V113\0\0\0");
+
+/// Where the game's keys come from: the keypad, recorded as it is read,
+/// or a replay, after which control returns to the keypad.
+enum Session<'b> {
+	Recording(Recorder<'b, Keypad>),
+	Playback(Playback<'b>),
+}
+
+impl<'b> KeySource for Session<'b> {
+	fn read(&mut self) -> Keys {
+		match self {
+			Session::Recording(recorder) => recorder.read(),
+			Session::Playback(playback) if !playback.is_finished() => playback.read(),
+			Session::Playback(_) => Keypad.read(),
+		}
+	}
+}
+
+/// Loads the replay in save memory, reading its roster into `roster` and checking that the chapter it was recorded in exists.
+fn load_replay<'b>(
+	buffer: &'b mut [u8; PAYLOAD_SIZE],
+	roster: &mut UnitList<'static>,
+) -> Option<(ReplayHeader, Playback<'b>)> {
+	let replay = replay::load_replay(&Sram, buffer).and_then(|replay| Playback::new(replay, NAMES, roster));
+	let (header, playback) = match replay {
+		Ok(replay) => replay,
+		Err(error) => {
+			println!("failed to load replay: {error}");
+			return None;
+		}
+	};
+	if CHAPTERS.get(header.chapter as usize).is_none() {
+		println!("replay is of chapter {}, which does not exist", header.chapter);
+		return None;
+	}
+	Some((header, playback))
+}
+
+fn rotate_rgb_color(color: Color) -> Color {
+	match (color.red(), color.green(), color.blue()) {
+		// Green from Red
+		(last, 0x1F, 0x00) if last > 0 => color.with_red(color.red() - 1),
+		// Blue from Green
+		(0x00, last, 0x1F) if last > 0 => color.with_green(color.green() - 1),
+		// Red from Blue
+		(0x1F, 0x00, last) if last > 0 => color.with_blue(color.blue() - 1),
+		// Red to Green
+		(0x1F, _, 0x00) => color.with_green(color.green() + 1),
+		// Green to Blue
+		(0x00, 0x1F, _) => color.with_blue(color.blue() + 1),
+		// Blue to Red
+		(_, 0x00, 0x1F) => color.with_red(color.red() + 1),
+		// If the state is invalid, reset to red.
+		_ => Color::RED,
+	}
+}
+
+#[no_mangle]
+extern "C" fn main() -> ! {
+	mmio::DISPCNT.write(
+		DisplayControl::new()
+			.with_video_mode(VideoMode0)
+			.with_show_bg0(true)
+			.with_show_bg1(true)
+			.with_show_bg2(true)
+			.with_show_obj(true)
+			.with_obj_vram_1d(true),
+	);
+
+	mmio::BG0CNT.write(
+		BackgroundControl::new()
+			.with_charblock(0)
+			.with_screenblock(8)
+			.with_priority(2),
+	);
+
+	// The range overlay shares the map's tiles and sits between the map and the sprites.
+	mmio::BG1CNT.write(
+		BackgroundControl::new()
+			.with_charblock(0)
+			.with_screenblock(9)
+			.with_priority(1),
+	);
+
+	// Windows and text are drawn above everything but sprites.
+	mmio::BG2CNT.write(
+		BackgroundControl::new()
+			.with_charblock(0)
+			.with_screenblock(10)
+			.with_priority(0),
+	);
+
+	mmio::BLDCNT.write(
+		BlendControl::new()
+			.with_target1_bg1(true)
+			.with_target2_bg0(true)
+			.with_mode(ColorEffectMode::AlphaBlend),
+	);
+	mmio::BLDALPHA.write([8, 8]);
+
+	mmio::IF.write(IrqBits::new());
+	mmio::IE.write(IrqBits::new().with_vblank(true).with_hblank(true));
+	mmio::IME.write(true);
+	mmio::DISPSTAT.write(
+		DisplayStatus::new()
+			.with_irq_vblank(true)
+			.with_irq_hblank(true),
+	);
+
+	// Safety: this is the only reference to the buffer, and main never returns.
+	let buffer = unsafe { &mut *core::ptr::addr_of_mut!(REPLAY_BUFFER) };
+	let mut input = Input::new();
+	let mut oam = Oam::new();
+
+	// Safety: as with the replay buffer, this is the only reference, and main never returns.
+	let scenes = unsafe { &mut *core::ptr::addr_of_mut!(SCENES) };
+	// Holding L and R at boot plays back the replay in save memory, starting from its chapter's introduction.
+	let boot_keys = Keypad.read();
+	let replay = if boot_keys.l() && boot_keys.r() {
+		load_replay(buffer, &mut scenes.scenes_mut().roster)
+	} else {
+		None
+	};
+	let mut session = match replay {
+		Some((header, playback)) => {
+			input.frame = header.frame;
+			input.held = header.held;
+			scenes.scenes_mut().intro = Some(ChapterIntro::new(Gba, header.chapter, header.rng));
+			// The introduction has already been built.
+			scenes.push(|_| SceneKind::Intro);
+			Session::Playback(playback)
+		}
+		None => {
+			// Replays are played back without save memory, so that they can't overwrite the player's saves.
+			// Safety: as with the replay buffer, this is the only reference, and main never returns.
+			scenes.scenes_mut().storage = Some(unsafe { &mut *core::ptr::addr_of_mut!(SAVE_MEMORY) });
+			scenes.push(title::show_title);
+			Session::Recording(Recorder::new(Keypad, buffer))
+		}
+	};
+	// Enter the first scene now, so that it sees the first frame of input, just as it was recorded.
+	scenes.apply_transition();
+
+	loop {
+		input.update(&mut session);
+
+		// L + R + Select keeps the recording so far, to be sent along with a bug report.
+		if let Session::Recording(recorder) = &mut session {
+			if input.new.select() && input.held.l() && input.held.r() {
+				let full = recorder.is_full();
+				let saved = recorder.finish().and_then(|replay| replay::save_replay(&mut Sram, replay));
+				match saved {
+					Ok(()) if full => println!("saved replay, which was cut short when it filled up"),
+					Ok(()) => println!("saved replay"),
+					Err(error) => println!("failed to save replay: {error}"),
+				}
+			}
+		}
+
+		oam.clean();
+
+		scenes.tick(&input);
+		scenes.draw(&mut oam);
+
+		mmio::BG_PALETTE
+			.index(0)
+			.apply(|color| *color = rotate_rgb_color(*color));
+
+		//for i in 1..16 {
+		//	mmio::OBJ_PALETTE
+		//		.index(i)
+		//		.apply(|color| *color = rotate_rgb_color(*color));
+		//}
+
+		wait_vblank();
+		oam.commit(&Gba);
+		if scenes.apply_transition() {
+			// Each chapter is recorded on its own, so a replay only has to hold the chapter in progress.
+			let current = scenes.current();
+			let scenes = scenes.scenes();
+			if let (Session::Recording(recorder), Some(SceneKind::Intro), Some(intro)) =
+				(&mut session, current, &scenes.intro)
+			{
+				let header = ReplayHeader {
+					chapter: intro.chapter(),
+					rng: intro.rng,
+					frame: input.frame,
+					held: input.held,
+				};
+				recorder.restart(&header, &scenes.roster);
+			}
+		}
+	}
+}
+
+#[panic_handler]
+fn panic_handler(info: &core::panic::PanicInfo) -> ! {
+	let log_level = MgbaMessageLevel::Fatal;
+	if let Ok(mut logger) = MgbaBufferedLogger::try_new(log_level) {
+		writeln!(logger, "{info}").ok();
+	}
+	loop {}
+}
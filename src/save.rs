@@ -0,0 +1,528 @@
+use crate::ai::Behavior;
+use crate::class::ClassId;
+use crate::game::{GameState, LevelData, UnitData};
+use crate::item::{Inventory, Item, ItemId};
+use crate::rng::Rng;
+use crate::stats::Stats;
+use crate::unit::{Faction, Unit, UnitList};
+use crate::video::Video;
+use core::fmt;
+
+// Save layout
+//
+// SRAM is split into fixed-size slots: SAVE_SLOTS campaign saves followed by one suspend slot
+// and one replay slot (see replay.rs).
+// Each slot begins with a header:
+//   magic (4 bytes), version (u16), kind (u8), payload length (u16), payload checksum (u32)
+// followed by the payload. Every multi-byte value is little endian.
+// A slot is only loaded if its magic, version, kind, length and checksum all agree,
+// so a half-written or bit-rotted slot is reported instead of producing a broken game.
+
+pub const SRAM_SIZE: usize = 0x8000;
+pub const SLOT_SIZE: usize = 0x1000;
+pub const SAVE_SLOTS: usize = 3;
+/// Mid-chapter suspends have a slot of their own, after the campaign saves.
+pub const SUSPEND_SLOT: usize = SAVE_SLOTS;
+
+/// Increase this whenever the payload format changes, so that old saves are rejected rather than misread.
+pub const SAVE_VERSION: u16 = 2;
+const MAGIC: [u8; 4] = *b"FEMB";
+const HEADER_SIZE: usize = 13;
+pub const PAYLOAD_SIZE: usize = SLOT_SIZE - HEADER_SIZE;
+
+/// Somewhere saves can be kept. Offsets are in bytes from the start of save memory.
+pub trait Storage {
+	fn read(&self, offset: usize, buffer: &mut [u8]);
+	fn write(&mut self, offset: usize, data: &[u8]);
+	/// Total capacity, in bytes.
+	fn size(&self) -> usize;
+}
+
+/// A plain byte buffer, for keeping saves in memory or testing the format away from hardware.
+impl Storage for [u8] {
+	fn read(&self, offset: usize, buffer: &mut [u8]) {
+		buffer.copy_from_slice(&self[offset..offset + buffer.len()]);
+	}
+
+	fn write(&mut self, offset: usize, data: &[u8]) {
+		self[offset..offset + data.len()].copy_from_slice(data);
+	}
+
+	fn size(&self) -> usize {
+		self.len()
+	}
+}
+
+/// Arrays as well, since a slice can't be lent out as a `dyn Storage`.
+impl<const N: usize> Storage for [u8; N] {
+	fn read(&self, offset: usize, buffer: &mut [u8]) {
+		self[..].read(offset, buffer);
+	}
+
+	fn write(&mut self, offset: usize, data: &[u8]) {
+		self[..].write(offset, data);
+	}
+
+	fn size(&self) -> usize {
+		N
+	}
+}
+
+/// What a slot holds.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum SaveKind {
+	/// Progress between chapters: the roster, the next chapter and the RNG.
+	Campaign = 1,
+	/// A chapter in progress.
+	Suspend = 2,
+	/// A recording of the player's inputs, kept by `replay::save_replay`.
+	Replay = 3,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum SaveError {
+	/// The slot number is past the end of save memory.
+	NoSuchSlot,
+	/// Nothing has been saved in this slot.
+	Empty,
+	/// The slot was written by a different version of the game.
+	Version(u16),
+	/// The slot holds a different kind of save.
+	WrongKind,
+	/// The slot's contents don't match its checksum, or describe something impossible.
+	Corrupt,
+	/// The data doesn't fit in a slot.
+	TooLarge,
+}
+
+impl fmt::Display for SaveError {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		match self {
+			SaveError::NoSuchSlot => write!(f, "no such save slot"),
+			SaveError::Empty => write!(f, "save slot is empty"),
+			SaveError::Version(version) => {
+				write!(f, "save is from version {version}, expected {SAVE_VERSION}")
+			}
+			SaveError::WrongKind => write!(f, "save slot holds a different kind of save"),
+			SaveError::Corrupt => write!(f, "save data is corrupt"),
+			SaveError::TooLarge => write!(f, "save data does not fit in a slot"),
+		}
+	}
+}
+
+/// 32-bit FNV-1a. Not cryptographic, but any flipped bit or torn write will change it.
+pub fn checksum(data: &[u8]) -> u32 {
+	data.iter()
+		.fold(0x811C9DC5, |hash, byte| (hash ^ *byte as u32).wrapping_mul(0x01000193))
+}
+
+/// Builds a save payload. Writing past the end of the payload is remembered,
+/// and reported when the payload is finished.
+pub struct Writer {
+	buffer: [u8; PAYLOAD_SIZE],
+	len: usize,
+	overflowed: bool,
+}
+
+impl Default for Writer {
+	fn default() -> Self {
+		Self::new()
+	}
+}
+
+impl Writer {
+	pub fn new() -> Self {
+		Self {
+			buffer: [0; PAYLOAD_SIZE],
+			len: 0,
+			overflowed: false,
+		}
+	}
+
+	pub fn bytes(&mut self, data: &[u8]) {
+		match self.buffer.get_mut(self.len..self.len + data.len()) {
+			Some(space) => {
+				space.copy_from_slice(data);
+				self.len += data.len();
+			}
+			None => self.overflowed = true,
+		}
+	}
+
+	pub fn u8(&mut self, value: u8) {
+		self.bytes(&[value]);
+	}
+
+	pub fn bool(&mut self, value: bool) {
+		self.u8(value as u8);
+	}
+
+	pub fn u16(&mut self, value: u16) {
+		self.bytes(&value.to_le_bytes());
+	}
+
+	pub fn i16(&mut self, value: i16) {
+		self.bytes(&value.to_le_bytes());
+	}
+
+	pub fn u32(&mut self, value: u32) {
+		self.bytes(&value.to_le_bytes());
+	}
+
+	/// Returns the finished payload.
+	pub fn finish(&self) -> Result<&[u8], SaveError> {
+		if self.overflowed {
+			Err(SaveError::TooLarge)
+		} else {
+			Ok(&self.buffer[..self.len])
+		}
+	}
+}
+
+/// Reads values back out of a payload, in the order they were written.
+/// Running off the end of the payload means it is corrupt.
+pub struct Reader<'b> {
+	data: &'b [u8],
+	position: usize,
+}
+
+impl<'b> Reader<'b> {
+	pub fn new(data: &'b [u8]) -> Self {
+		Self { data, position: 0 }
+	}
+
+	pub fn bytes(&mut self, len: usize) -> Result<&'b [u8], SaveError> {
+		let bytes = self
+			.data
+			.get(self.position..self.position + len)
+			.ok_or(SaveError::Corrupt)?;
+		self.position += len;
+		Ok(bytes)
+	}
+
+	/// Returns everything which hasn't been read yet.
+	pub fn rest(&mut self) -> &'b [u8] {
+		let rest = &self.data[self.position..];
+		self.position = self.data.len();
+		rest
+	}
+
+	fn array<const N: usize>(&mut self) -> Result<[u8; N], SaveError> {
+		Ok(self.bytes(N)?.try_into().unwrap())
+	}
+
+	pub fn u8(&mut self) -> Result<u8, SaveError> {
+		Ok(self.array::<1>()?[0])
+	}
+
+	pub fn bool(&mut self) -> Result<bool, SaveError> {
+		match self.u8()? {
+			0 => Ok(false),
+			1 => Ok(true),
+			_ => Err(SaveError::Corrupt),
+		}
+	}
+
+	pub fn u16(&mut self) -> Result<u16, SaveError> {
+		Ok(u16::from_le_bytes(self.array()?))
+	}
+
+	pub fn i16(&mut self) -> Result<i16, SaveError> {
+		Ok(i16::from_le_bytes(self.array()?))
+	}
+
+	pub fn u32(&mut self) -> Result<u32, SaveError> {
+		Ok(u32::from_le_bytes(self.array()?))
+	}
+}
+
+fn slot_offset(storage: &(impl Storage + ?Sized), slot: usize) -> Result<usize, SaveError> {
+	let offset = slot * SLOT_SIZE;
+	if offset + SLOT_SIZE > storage.size() {
+		Err(SaveError::NoSuchSlot)
+	} else {
+		Ok(offset)
+	}
+}
+
+/// Writes a payload and its header to a slot.
+/// The header is written last, so an interrupted save fails its checksum instead of loading.
+pub fn write_slot(
+	storage: &mut (impl Storage + ?Sized),
+	slot: usize,
+	kind: SaveKind,
+	payload: &[u8],
+) -> Result<(), SaveError> {
+	let offset = slot_offset(storage, slot)?;
+	if payload.len() > PAYLOAD_SIZE {
+		return Err(SaveError::TooLarge);
+	}
+
+	// Invalidate the old header first, in case the payload write is cut short.
+	storage.write(offset, &[0; 4]);
+	storage.write(offset + HEADER_SIZE, payload);
+
+	let mut header = [0; HEADER_SIZE];
+	header[0..4].copy_from_slice(&MAGIC);
+	header[4..6].copy_from_slice(&SAVE_VERSION.to_le_bytes());
+	header[6] = kind as u8;
+	header[7..9].copy_from_slice(&(payload.len() as u16).to_le_bytes());
+	header[9..13].copy_from_slice(&checksum(payload).to_le_bytes());
+	storage.write(offset, &header);
+	Ok(())
+}
+
+/// Reads a slot's payload into `buffer`, verifying its header and checksum.
+pub fn read_slot<'b>(
+	storage: &(impl Storage + ?Sized),
+	slot: usize,
+	kind: SaveKind,
+	buffer: &'b mut [u8; PAYLOAD_SIZE],
+) -> Result<Reader<'b>, SaveError> {
+	let offset = slot_offset(storage, slot)?;
+	let mut header = [0; HEADER_SIZE];
+	storage.read(offset, &mut header);
+
+	if header[0..4] != MAGIC {
+		return Err(SaveError::Empty);
+	}
+	let version = u16::from_le_bytes([header[4], header[5]]);
+	if version != SAVE_VERSION {
+		return Err(SaveError::Version(version));
+	}
+	if header[6] != kind as u8 {
+		return Err(SaveError::WrongKind);
+	}
+	let len = u16::from_le_bytes([header[7], header[8]]) as usize;
+	if len > PAYLOAD_SIZE {
+		return Err(SaveError::Corrupt);
+	}
+	let payload = &mut buffer[..len];
+	storage.read(offset + HEADER_SIZE, payload);
+	if checksum(payload) != u32::from_le_bytes([header[9], header[10], header[11], header[12]]) {
+		return Err(SaveError::Corrupt);
+	}
+	Ok(Reader::new(payload))
+}
+
+/// Empties a slot.
+pub fn erase_slot(storage: &mut (impl Storage + ?Sized), slot: usize) -> Result<(), SaveError> {
+	let offset = slot_offset(storage, slot)?;
+	storage.write(offset, &[0; HEADER_SIZE]);
+	Ok(())
+}
+
+pub fn write_rng(writer: &mut Writer, rng: &Rng) {
+	writer.bytes(&rng.to_bytes());
+}
+
+pub fn read_rng(reader: &mut Reader) -> Result<Rng, SaveError> {
+	Rng::from_bytes(reader.array()?).ok_or(SaveError::Corrupt)
+}
+
+fn write_stats(writer: &mut Writer, stats: &Stats) {
+	writer.bytes(&stats.to_array());
+}
+
+fn read_stats(reader: &mut Reader) -> Result<Stats, SaveError> {
+	Ok(Stats::from_array(reader.array()?))
+}
+
+pub fn read_faction(reader: &mut Reader) -> Result<Faction, SaveError> {
+	match reader.u8()? {
+		0 => Ok(Faction::Player),
+		1 => Ok(Faction::Enemy),
+		2 => Ok(Faction::Other),
+		_ => Err(SaveError::Corrupt),
+	}
+}
+
+fn read_behavior(reader: &mut Reader) -> Result<Behavior, SaveError> {
+	match reader.u8()? {
+		0 => Ok(Behavior::Aggressive),
+		1 => Ok(Behavior::InRange),
+		2 => Ok(Behavior::Guard),
+		3 => Ok(Behavior::Retreat),
+		_ => Err(SaveError::Corrupt),
+	}
+}
+
+/// Records a unit. Its name is stored as text, and must be found again when loading.
+pub fn write_unit(writer: &mut Writer, unit: &Unit) {
+	writer.u8(unit.name.len() as u8);
+	writer.bytes(unit.name.as_bytes());
+	writer.u8(unit.class as u8);
+	writer.u8(unit.level);
+	writer.u8(unit.exp);
+	writer.bool(unit.is_boss);
+	writer.u8(unit.faction as u8);
+	writer.u8(unit.behavior as u8);
+	writer.bool(unit.has_acted);
+	writer.i16(unit.position.x);
+	writer.i16(unit.position.y);
+	write_stats(writer, &unit.stats);
+	write_stats(writer, &unit.growth_progress);
+	writer.u8(unit.hp);
+	writer.u8(unit.inventory.len() as u8);
+	for (_, item) in unit.inventory.iter() {
+		writer.u8(item.id as u8);
+		writer.u8(item.uses);
+	}
+}
+
+/// Restores a unit saved by `write_unit`.
+/// Names can't be stored in save memory, so the unit's name must be one of `names`.
+pub fn read_unit<'a>(reader: &mut Reader, names: &[&'a str]) -> Result<Unit<'a>, SaveError> {
+	let len = reader.u8()? as usize;
+	let name = reader.bytes(len)?;
+	let name = *names
+		.iter()
+		.find(|candidate| candidate.as_bytes() == name)
+		.ok_or(SaveError::Corrupt)?;
+	let class = *ClassId::ALL
+		.get(reader.u8()? as usize)
+		.ok_or(SaveError::Corrupt)?;
+	let level = reader.u8()?;
+	let exp = reader.u8()?;
+	let is_boss = reader.bool()?;
+	let faction = read_faction(reader)?;
+	let behavior = read_behavior(reader)?;
+
+	let mut unit = Unit::new(&UnitData {
+		name,
+		class,
+		x: 0,
+		y: 0,
+		is_boss,
+		level,
+		faction,
+		items: &[],
+		behavior,
+	});
+	unit.exp = exp;
+	unit.has_acted = reader.bool()?;
+	unit.position.x = reader.i16()?;
+	unit.position.y = reader.i16()?;
+	unit.stats = read_stats(reader)?;
+	unit.growth_progress = read_stats(reader)?;
+	unit.hp = reader.u8()?;
+
+	let mut inventory = Inventory::new();
+	for _ in 0..reader.u8()? {
+		let id = *ItemId::ALL
+			.get(reader.u8()? as usize)
+			.ok_or(SaveError::Corrupt)?;
+		let uses = reader.u8()?;
+		inventory
+			.push(Item { id, uses })
+			.map_err(|_| SaveError::Corrupt)?;
+	}
+	unit.inventory = inventory;
+	Ok(unit)
+}
+
+pub fn write_units(writer: &mut Writer, units: &UnitList) {
+	writer.u8(units.len() as u8);
+	for (_, unit) in units.iter() {
+		write_unit(writer, unit);
+	}
+}
+
+/// Reads units into `units`, replacing any it held.
+pub fn read_units<'a>(reader: &mut Reader, names: &[&'a str], units: &mut UnitList<'a>) -> Result<(), SaveError> {
+	units.clear();
+	for _ in 0..reader.u8()? {
+		units
+			.push(read_unit(reader, names)?)
+			.map_err(|_| SaveError::Corrupt)?;
+	}
+	Ok(())
+}
+
+/// Progress through the campaign, saved between chapters along with the player's surviving units.
+pub struct CampaignSave {
+	/// Index of the next chapter to play.
+	pub chapter: u8,
+	pub rng: Rng,
+}
+
+pub fn save_campaign(
+	storage: &mut (impl Storage + ?Sized),
+	slot: usize,
+	save: &CampaignSave,
+	roster: &UnitList,
+) -> Result<(), SaveError> {
+	if slot >= SAVE_SLOTS {
+		return Err(SaveError::NoSuchSlot);
+	}
+	let mut writer = Writer::new();
+	writer.u8(save.chapter);
+	write_rng(&mut writer, &save.rng);
+	write_units(&mut writer, roster);
+	write_slot(storage, slot, SaveKind::Campaign, writer.finish()?)
+}
+
+/// Loads a campaign save, reading its units into `roster`. Every unit's name must be one of `names`.
+/// If loading fails, `roster` may have been partly overwritten.
+pub fn load_campaign<'a>(
+	storage: &(impl Storage + ?Sized),
+	slot: usize,
+	names: &[&'a str],
+	roster: &mut UnitList<'a>,
+) -> Result<CampaignSave, SaveError> {
+	if slot >= SAVE_SLOTS {
+		return Err(SaveError::NoSuchSlot);
+	}
+	let mut buffer = [0; PAYLOAD_SIZE];
+	let mut reader = read_slot(storage, slot, SaveKind::Campaign, &mut buffer)?;
+	let save = CampaignSave {
+		chapter: reader.u8()?,
+		rng: read_rng(&mut reader)?,
+	};
+	read_units(&mut reader, names, roster)?;
+	Ok(save)
+}
+
+/// Returns the chapter a campaign save continues from, without loading it.
+pub fn saved_chapter(storage: &(impl Storage + ?Sized), slot: usize) -> Result<u8, SaveError> {
+	if slot >= SAVE_SLOTS {
+		return Err(SaveError::NoSuchSlot);
+	}
+	let mut buffer = [0; PAYLOAD_SIZE];
+	read_slot(storage, slot, SaveKind::Campaign, &mut buffer)?.u8()
+}
+
+/// Records a chapter in progress, so that it can be resumed later.
+pub fn suspend<V: Video>(
+	storage: &mut (impl Storage + ?Sized),
+	chapter: u8,
+	game: &GameState<V>,
+) -> Result<(), SaveError> {
+	let mut writer = Writer::new();
+	writer.u8(chapter);
+	game.write_suspend(&mut writer);
+	write_slot(storage, SUSPEND_SLOT, SaveKind::Suspend, writer.finish()?)
+}
+
+/// Returns the chapter a suspend was made in, without loading it.
+/// The caller uses this to find the chapter's level before calling `resume`.
+pub fn suspended_chapter(storage: &(impl Storage + ?Sized)) -> Result<u8, SaveError> {
+	let mut buffer = [0; PAYLOAD_SIZE];
+	read_slot(storage, SUSPEND_SLOT, SaveKind::Suspend, &mut buffer)?.u8()
+}
+
+/// Resumes a suspended chapter on `level`, which must be the level it was suspended on.
+/// Every unit's name must be one of `names`.
+pub fn resume<'a, V: Video>(
+	storage: &(impl Storage + ?Sized),
+	video: V,
+	level: &'a LevelData<'a>,
+	names: &[&'a str],
+) -> Result<GameState<'a, V>, SaveError> {
+	let mut buffer = [0; PAYLOAD_SIZE];
+	let mut reader = read_slot(storage, SUSPEND_SLOT, SaveKind::Suspend, &mut buffer)?;
+	let chapter = reader.u8()?;
+	let mut game = GameState::read_suspend(video, level, names, &mut reader)?;
+	game.chapter = Some(chapter);
+	Ok(game)
+}
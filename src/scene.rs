@@ -0,0 +1,207 @@
+use crate::campaign::ChapterIntro;
+use crate::console::{Input, Oam, Vram};
+use crate::game::{self, GameState};
+use crate::save::Storage;
+use crate::title::TitleScene;
+use crate::unit::UnitList;
+use crate::video::Video;
+
+/// What a scene wants to happen after its tick.
+/// Transitions are deferred until VBlank, so a scene always finishes its frame.
+pub enum Transition<'a, V: Video> {
+	None,
+	/// Pauses the current scene, then builds a new one and enters it on top.
+	Push(Builder<'a, V>),
+	/// Exits the current scene and resumes the one beneath it.
+	Pop,
+	/// Exits the current scene, then builds a new one and enters it in its place.
+	Replace(Builder<'a, V>),
+}
+
+/// Builds a scene in its slot and returns its kind.
+/// The scene which asked for it is still in its own slot, so anything it hands over can be read from there.
+pub type Builder<'a, V> = fn(&mut Scenes<'a, V>) -> SceneKind;
+
+/// A self-contained part of the game, such as the title screen or a chapter's map.
+pub trait Scene<'a, V: Video> {
+	/// Called whenever the scene becomes the topmost scene, including when it is resumed.
+	/// VRAM and every screenblock have been cleared, so all graphics must be (re)loaded here.
+	fn enter(&mut self, vram: &mut Vram<V>);
+	/// Called when the scene stops being the topmost scene.
+	fn exit(&mut self) {}
+	fn tick(&mut self, input: &Input) -> Transition<'a, V>;
+	/// Reserves the scene's sprites for this frame.
+	fn draw(&mut self, oam: &mut Oam);
+}
+
+/// Every kind of scene. Each kind has a slot of its own in `Scenes`.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum SceneKind {
+	Title,
+	Intro,
+	Map,
+}
+
+/// A slot for each kind of scene. Without an allocator, scenes can't be boxed,
+/// and a chapter is too large to be passed around by value, so scenes are built directly in their slots.
+/// This means that each kind of scene can only be on the stack once.
+pub struct Scenes<'a, V: Video> {
+	pub video: V,
+	/// Where the campaign is saved and chapters are suspended. Without it, nothing is saved.
+	pub storage: Option<&'a mut dyn Storage>,
+	/// The player's units between chapters: those who survived the last one, ready for the next.
+	/// Kept here rather than in a scene, so that it is never moved from one scene to the next.
+	pub roster: UnitList<'a>,
+	pub title: Option<TitleScene<V>>,
+	pub intro: Option<ChapterIntro<V>>,
+	pub map: Option<GameState<'a, V>>,
+}
+
+impl<'a, V: Video> Scenes<'a, V> {
+	fn get(&mut self, kind: SceneKind) -> Option<&mut dyn Scene<'a, V>> {
+		match kind {
+			SceneKind::Title => Some(self.title.as_mut()?),
+			SceneKind::Intro => Some(self.intro.as_mut()?),
+			SceneKind::Map => Some(self.map.as_mut()?),
+		}
+	}
+
+	fn clear(&mut self, kind: SceneKind) {
+		match kind {
+			SceneKind::Title => self.title = None,
+			SceneKind::Intro => self.intro = None,
+			SceneKind::Map => self.map = None,
+		}
+	}
+}
+
+/// The deepest scenes can be nested. Each kind of scene can only be on the stack once.
+pub const MAX_SCENES: usize = 3;
+
+/// Owns every active scene. Only the topmost scene is ticked and drawn.
+pub struct SceneStack<'a, V: Video> {
+	scenes: Scenes<'a, V>,
+	/// The kind of each scene on the stack, from the bottom up.
+	stack: [SceneKind; MAX_SCENES],
+	len: usize,
+	pending: Transition<'a, V>,
+	vram: Vram<V>,
+}
+
+impl<'a, V: Video> SceneStack<'a, V> {
+	/// Creates a stack which builds and enters `root` at the next VBlank.
+	pub fn new(video: V, root: Builder<'a, V>) -> Self {
+		let mut stack = Self::empty(video);
+		stack.push(root);
+		stack
+	}
+
+	/// Creates a stack with no scenes. Being const, this can initialize a static.
+	pub const fn empty(video: V) -> Self {
+		Self {
+			scenes: Scenes {
+				video,
+				storage: None,
+				roster: UnitList::new(),
+				title: None,
+				intro: None,
+				map: None,
+			},
+			stack: [SceneKind::Title; MAX_SCENES],
+			len: 0,
+			pending: Transition::None,
+			vram: Vram::new(video),
+		}
+	}
+
+	/// Builds a scene and enters it on top of the stack at the next VBlank,
+	/// unless another transition is already pending.
+	pub fn push(&mut self, build: Builder<'a, V>) {
+		if matches!(self.pending, Transition::None) {
+			self.pending = Transition::Push(build);
+		}
+	}
+
+	fn top(&mut self) -> Option<&mut dyn Scene<'a, V>> {
+		let top = self.len.checked_sub(1)?;
+		self.scenes.get(self.stack[top])
+	}
+
+	/// Returns the kind of the topmost scene, if any has been entered.
+	pub fn current(&self) -> Option<SceneKind> {
+		Some(self.stack[self.len.checked_sub(1)?])
+	}
+
+	/// Every scene's slot, including those which aren't on the stack.
+	pub fn scenes(&self) -> &Scenes<'a, V> {
+		&self.scenes
+	}
+
+	/// Every scene's slot. A scene may be built here before it is pushed,
+	/// such as one which needs more than a `Builder` can get hold of.
+	pub fn scenes_mut(&mut self) -> &mut Scenes<'a, V> {
+		&mut self.scenes
+	}
+
+	pub fn tick(&mut self, input: &Input) {
+		// Only the first transition requested before VBlank is honoured.
+		if !matches!(self.pending, Transition::None) {
+			return;
+		}
+		if let Some(scene) = self.top() {
+			self.pending = scene.tick(input);
+		}
+	}
+
+	pub fn draw(&mut self, oam: &mut Oam) {
+		if let Some(scene) = self.top() {
+			scene.draw(oam);
+		}
+	}
+
+	/// Performs any pending transition. This should be called during VBlank;
+	/// the display is blanked while graphics are reloaded so that half-loaded scenes are never shown.
+	/// Returns true if there was a transition, in which case `current` has just been entered.
+	pub fn apply_transition(&mut self) -> bool {
+		let transition = core::mem::replace(&mut self.pending, Transition::None);
+		// Where the new topmost scene goes: above the current one, or in its place.
+		let (position, build) = match transition {
+			Transition::None => return false,
+			Transition::Push(build) => (self.len, Some(build)),
+			Transition::Pop => (self.len.saturating_sub(1), None),
+			Transition::Replace(build) => (self.len.saturating_sub(1), Some(build)),
+		};
+
+		let video = self.vram.video;
+		video.set_forced_blank(true);
+
+		if let Some(scene) = self.top() {
+			scene.exit();
+		}
+		let exited = self.current();
+		let entered = build.map(|build| build(&mut self.scenes));
+		// The scene beneath stays in its slot, so no other scene can use it.
+		if let Some(kind) = entered {
+			assert!(position < MAX_SCENES, "scene stack overflow");
+			assert!(!self.stack[..position].contains(&kind), "{kind:?} is already on the scene stack");
+			self.stack[position] = kind;
+		}
+		if let Some(exited) = exited.filter(|exited| position < self.len && Some(*exited) != entered) {
+			self.scenes.clear(exited);
+		}
+		self.len = position + entered.is_some() as usize;
+
+		self.vram.reset();
+		for screenblock in [game::MAP_SCREENBLOCK, game::OVERLAY_SCREENBLOCK, game::TEXT_SCREENBLOCK] {
+			game::clear_screenblock(video, screenblock);
+		}
+		if let Some(top) = self.len.checked_sub(1) {
+			if let Some(scene) = self.scenes.get(self.stack[top]) {
+				scene.enter(&mut self.vram);
+			}
+		}
+
+		video.set_forced_blank(false);
+		true
+	}
+}
@@ -0,0 +1,50 @@
+/// A unit's permanent statistics.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub struct Stats {
+	/// Maximum hit points.
+	pub hp: u8,
+	pub strength: u8,
+	pub magic: u8,
+	pub skill: u8,
+	pub speed: u8,
+	pub luck: u8,
+	pub defense: u8,
+	pub resistance: u8,
+}
+
+impl Stats {
+	/// Number of fields, and the length of the array returned by `to_array`.
+	pub const COUNT: usize = 8;
+
+	/// Fills every field with the same value.
+	pub const fn splat(value: u8) -> Self {
+		Self::from_array([value; Self::COUNT])
+	}
+
+	/// Returns each stat, in declaration order.
+	pub const fn to_array(&self) -> [u8; Self::COUNT] {
+		[
+			self.hp,
+			self.strength,
+			self.magic,
+			self.skill,
+			self.speed,
+			self.luck,
+			self.defense,
+			self.resistance,
+		]
+	}
+
+	pub const fn from_array(stats: [u8; Self::COUNT]) -> Self {
+		Self {
+			hp: stats[0],
+			strength: stats[1],
+			magic: stats[2],
+			skill: stats[3],
+			speed: stats[4],
+			luck: stats[5],
+			defense: stats[6],
+			resistance: stats[7],
+		}
+	}
+}
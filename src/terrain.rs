@@ -0,0 +1,48 @@
+use crate::tools::include_resource;
+
+/// Determines how expensive each type of terrain is to move across.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum MovementClass {
+	Foot,
+	Armor,
+	Mounted,
+	Flying,
+}
+
+#[derive(Debug)]
+pub struct Terrain {
+	pub name: &'static str,
+	/// Movement points needed to enter this terrain, indexed by MovementClass.
+	/// None if the terrain cannot be entered.
+	pub movement_cost: [Option<u8>; 4],
+	/// Added to the defense and resistance of units standing on this terrain.
+	pub defense: i8,
+	/// Added to the avoid of units standing on this terrain.
+	pub avoid: i8,
+	/// Percentage of max HP restored to units standing on this terrain at the start of their turn.
+	pub heal: u8,
+}
+
+impl Terrain {
+	pub fn movement_cost(&self, class: MovementClass) -> Option<u8> {
+		self.movement_cost[class as usize]
+	}
+}
+
+#[derive(Debug)]
+pub struct TerrainTable {
+	pub terrain: &'static [Terrain],
+	/// Maps each tileset index to an index into `terrain`.
+	pub tiles: &'static [u8],
+}
+
+impl TerrainTable {
+	/// Returns the terrain used by a tileset index, if it has one.
+	pub fn from_tile(&self, tile: u8) -> Option<&'static Terrain> {
+		let id = *self.tiles.get(tile as usize)?;
+		self.terrain.get(id as usize)
+	}
+}
+
+/// Compiled from `assets/terrain.toml`.
+pub const TERRAIN: TerrainTable = include_resource!("terrain.rs");
@@ -0,0 +1,97 @@
+use crate::assets;
+use crate::console::Vram;
+use crate::video::{TileEntry, Video};
+use core::fmt;
+
+/// Draws ASCII text and windows onto a background layer using `gfx/font`.
+/// Every glyph is opaque, so any text drawn also acts as a window behind itself.
+pub struct TextLayer<V: Video> {
+	video: V,
+	screenblock: usize,
+	font_id: u16,
+	palette: u16,
+}
+
+impl<V: Video> TextLayer<V> {
+	/// Creates a layer which draws to `screenblock`. Nothing can be drawn until the font is loaded.
+	pub fn new(video: V, screenblock: usize) -> Self {
+		Self {
+			video,
+			screenblock,
+			font_id: 0,
+			palette: 0,
+		}
+	}
+
+	/// Loads the font into VRAM and clears the layer.
+	pub fn load(&mut self, vram: &mut Vram<V>) {
+		let font = assets::gfx::FONT;
+		self.font_id = vram.load_4bpp_bg_texture(font.tiles());
+		self.palette = vram.load_bg_palette(font.palette());
+		self.clear();
+	}
+
+	/// Places a single character. Characters outside of printable ASCII are drawn as '?'.
+	pub fn put(&self, x: usize, y: usize, c: char) {
+		let c = if (' '..='~').contains(&c) { c } else { '?' };
+		if x < 32 && y < 32 {
+			self.video.set_tile(
+				self.screenblock,
+				y,
+				x,
+				TileEntry::new(self.font_id + assets::gfx::FONT.tile(c as usize - 0x20), self.palette),
+			);
+		}
+	}
+
+	/// Draws an empty window.
+	pub fn fill(&self, x: usize, y: usize, width: usize, height: usize) {
+		for row in y..y + height {
+			for col in x..x + width {
+				self.put(col, row, ' ');
+			}
+		}
+	}
+
+	/// Returns a writer which prints text starting at the given tile,
+	/// for use with `write!`. Newlines return to the starting column.
+	pub fn at(&self, x: usize, y: usize) -> TextWriter<'_, V> {
+		TextWriter {
+			layer: self,
+			left: x,
+			x,
+			y,
+		}
+	}
+
+	/// Removes all text and windows.
+	pub fn clear(&self) {
+		for row in 0..32 {
+			for col in 0..32 {
+				self.video.set_tile(self.screenblock, row, col, TileEntry::BLANK);
+			}
+		}
+	}
+}
+
+pub struct TextWriter<'a, V: Video> {
+	layer: &'a TextLayer<V>,
+	left: usize,
+	x: usize,
+	y: usize,
+}
+
+impl<'a, V: Video> fmt::Write for TextWriter<'a, V> {
+	fn write_str(&mut self, s: &str) -> fmt::Result {
+		for c in s.chars() {
+			if c == '\n' {
+				self.x = self.left;
+				self.y += 1;
+			} else {
+				self.layer.put(self.x, self.y, c);
+				self.x += 1;
+			}
+		}
+		Ok(())
+	}
+}
@@ -0,0 +1,134 @@
+use crate::campaign::{ChapterIntro, CAMPAIGN_SLOT, CHAPTERS, NAMES};
+use crate::console::{Input, Oam, Vram};
+use crate::game::TEXT_SCREENBLOCK;
+use crate::menu::{Menu, MenuEvent};
+use crate::rng::Rng;
+use crate::save::{self, SaveError};
+use crate::scene::{Scene, SceneKind, Scenes, Transition};
+use crate::text::TextLayer;
+use crate::video::Video;
+use core::fmt::Write;
+
+/// Options offered by the title screen when there is a save to continue from.
+const OPTIONS: [&str; 2] = ["New Game", "Continue"];
+const NEW_GAME: usize = 0;
+const CONTINUE: usize = 1;
+
+/// The first thing the player sees. Pressing start begins the campaign,
+/// and the title screen returns once the campaign is over or a chapter is suspended.
+pub struct TitleScene<V: Video> {
+	text: TextLayer<V>,
+	/// Chooses between a new game and continuing, if there is a save to continue from.
+	menu: Option<Menu>,
+	/// The chapter the campaign begins from.
+	pub chapter: u8,
+	/// Each chapter's RNG starts from this, before the player's inputs are mixed in.
+	pub rng: Rng,
+}
+
+impl<V: Video> TitleScene<V> {
+	pub fn new(video: V) -> Self {
+		Self {
+			text: TextLayer::new(video, TEXT_SCREENBLOCK),
+			menu: None,
+			chapter: 0,
+			rng: Rng::new(0),
+		}
+	}
+
+	/// Offers to continue the campaign as well as starting a new one.
+	pub fn with_continue(self) -> Self {
+		Self {
+			menu: Some(Menu::new(OPTIONS.len())),
+			..self
+		}
+	}
+
+	fn draw_menu(&self) {
+		if let Some(menu) = &self.menu {
+			menu.draw(&self.text, 9, 12, 12, |i, text| {
+				write!(text, "{}", OPTIONS[i]).ok();
+			});
+		}
+	}
+}
+
+impl<'a, V: Video> Scene<'a, V> for TitleScene<V> {
+	fn enter(&mut self, vram: &mut Vram<V>) {
+		self.text.load(vram);
+		self.text.fill(7, 6, 16, 3);
+		write!(self.text.at(8, 7), "Furry  Emblem").ok();
+		if self.menu.is_some() {
+			self.draw_menu();
+		} else {
+			write!(self.text.at(9, 13), "Press Start").ok();
+		}
+	}
+
+	fn tick(&mut self, input: &Input) -> Transition<'a, V> {
+		let chosen = match &mut self.menu {
+			Some(menu) => match menu.update(input) {
+				Some(MenuEvent::Confirm(option)) => Some(option),
+				_ if input.new.start() => Some(menu.selected),
+				_ => None,
+			},
+			None => (input.new.start() || input.new.a()).then_some(NEW_GAME),
+		};
+		self.draw_menu();
+
+		match chosen {
+			Some(CONTINUE) => Transition::Replace(continue_campaign),
+			Some(_) => {
+				// Seed the campaign from how long the player lingered on the title screen.
+				self.rng.mix(input.entropy());
+				Transition::Replace(start_campaign)
+			}
+			None => Transition::None,
+		}
+	}
+
+	fn draw(&mut self, _oam: &mut Oam) {}
+}
+
+/// Shows the title screen, offering to continue if there is a suspended chapter or a campaign save.
+pub fn show_title<V: Video>(scenes: &mut Scenes<V>) -> SceneKind {
+	let title = TitleScene::new(scenes.video);
+	let saved = scenes.storage.as_deref().is_some_and(|storage| {
+		save::suspended_chapter(storage).is_ok() || save::saved_chapter(storage, CAMPAIGN_SLOT).is_ok()
+	});
+	scenes.title = Some(if saved { title.with_continue() } else { title });
+	SceneKind::Title
+}
+
+/// Introduces the title screen's first chapter.
+fn start_campaign<V: Video>(scenes: &mut Scenes<V>) -> SceneKind {
+	let title = scenes.title.as_ref().expect("the campaign begins from the title screen");
+	scenes.roster.clear();
+	scenes.intro = Some(ChapterIntro::new(scenes.video, title.chapter, title.rng));
+	SceneKind::Intro
+}
+
+/// Resumes the suspended chapter, or failing that introduces the chapter the campaign was saved before.
+/// If neither can be loaded, the title screen stays.
+fn continue_campaign<V: Video>(scenes: &mut Scenes<V>) -> SceneKind {
+	let Some(storage) = scenes.storage.as_deref() else {
+		return SceneKind::Title;
+	};
+
+	let resumed = save::suspended_chapter(storage).and_then(|chapter| {
+		let level = CHAPTERS.get(chapter as usize).ok_or(SaveError::Corrupt)?.map;
+		save::resume(storage, scenes.video, level, NAMES)
+	});
+	if let Ok(game) = resumed {
+		scenes.map = Some(game);
+		return SceneKind::Map;
+	}
+
+	match save::load_campaign(storage, CAMPAIGN_SLOT, NAMES, &mut scenes.roster) {
+		Ok(campaign) if CHAPTERS.get(campaign.chapter as usize).is_some() => {
+			scenes.intro = Some(ChapterIntro::new(scenes.video, campaign.chapter, campaign.rng));
+			SceneKind::Intro
+		}
+		_ => SceneKind::Title,
+	}
+}
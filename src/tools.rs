@@ -1,12 +1,3 @@
-/// Includes a resource from cargo's output directory.
-macro_rules! include_aligned_resource {
-	($file:expr $(,)?) => {
-		gba::include_aligned_bytes!(concat!(env!("OUT_DIR"), "/assets/", $file))
-	};
-}
-
-pub(crate) use include_aligned_resource;
-
 /// Includes a resource from cargo's output directory.
 macro_rules! include_resource {
 	($file:expr $(,)?) => {
@@ -16,10 +7,57 @@ macro_rules! include_resource {
 
 pub(crate) use include_resource;
 
-macro_rules! load_level {
-	($file:expr $(,)?) => {
-		include!(concat!(env!("OUT_DIR"), "/assets/maps/", $file, ".rs"))
+/// Forces 4-byte alignment on included data, so that it can be copied to VRAM a word at a time.
+#[repr(C, align(4))]
+pub struct Aligned<T: ?Sized>(pub T);
+
+/// A converted image: its 4bpp tiles, and the palette they use.
+pub struct Image {
+	pub tiles: &'static Aligned<[u8]>,
+	pub palette: &'static Aligned<[u8]>,
+	/// Where each of the image's tiles was stored, since repeated tiles may have been removed.
+	pub map: &'static Aligned<[u8]>,
+	/// Size of each tile in pixels, as set by the image's manifest.
+	pub tile_width: u8,
+	pub tile_height: u8,
+	/// Images in the same group share one palette.
+	pub palette_group: Option<&'static str>,
+}
+
+impl Image {
+	pub fn tiles(&self) -> &'static [u32] {
+		let bytes = &self.tiles.0;
+		// Safety: Aligned guarantees the alignment, and the length is rounded down to whole words.
+		unsafe { core::slice::from_raw_parts(bytes.as_ptr().cast(), bytes.len() / 4) }
+	}
+
+	pub fn palette(&self) -> &'static [u16] {
+		let bytes = &self.palette.0;
+		// Safety: Aligned guarantees the alignment, and the length is rounded down to whole colors.
+		unsafe { core::slice::from_raw_parts(bytes.as_ptr().cast(), bytes.len() / 2) }
+	}
+
+	/// Returns the offset of a tile within `tiles()`, counted in 8x8 tiles.
+	/// Always use this rather than multiplying the index, since duplicate tiles may have been removed.
+	pub fn tile(&self, index: usize) -> u16 {
+		let map = &self.map.0;
+		let stored = u16::from_le_bytes([map[index * 2], map[index * 2 + 1]]);
+		stored * (self.tile_width / 8) as u16 * (self.tile_height / 8) as u16
+	}
+}
+
+/// Includes an image's tiles, palette and tile map from cargo's output directory.
+macro_rules! include_image {
+	($file:expr, $tile_width:expr, $tile_height:expr, $palette_group:expr $(,)?) => {
+		crate::tools::Image {
+			tiles: &crate::tools::Aligned(*include_bytes!(concat!(env!("OUT_DIR"), "/assets/", $file, ".4bpp"))),
+			palette: &crate::tools::Aligned(*include_bytes!(concat!(env!("OUT_DIR"), "/assets/", $file, ".pal"))),
+			map: &crate::tools::Aligned(*include_bytes!(concat!(env!("OUT_DIR"), "/assets/", $file, ".map"))),
+			tile_width: $tile_width,
+			tile_height: $tile_height,
+			palette_group: $palette_group,
+		}
 	};
 }
 
-pub(crate) use load_level;
+pub(crate) use include_image;
@@ -1,9 +1,10 @@
 #![allow(dead_code)]
 
-// Represents one of four cardinal directions.
 use core::fmt::Debug;
 use core::ops::*;
 
+/// Represents one of four cardinal directions.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
 pub enum Direction4 {
 	Up,
 	Right,
@@ -12,6 +13,23 @@ pub enum Direction4 {
 }
 
 impl Direction4 {
+	pub const ALL: [Direction4; 4] = [
+		Direction4::Up,
+		Direction4::Right,
+		Direction4::Down,
+		Direction4::Left,
+	];
+
+	/// Returns a vector of length 1 pointing in this direction.
+	pub fn to_vector(self) -> Vector2D<i16> {
+		match self {
+			Direction4::Up => Vector2D { x: 0, y: -1 },
+			Direction4::Right => Vector2D { x: 1, y: 0 },
+			Direction4::Down => Vector2D { x: 0, y: 1 },
+			Direction4::Left => Vector2D { x: -1, y: 0 },
+		}
+	}
+
 	pub fn rotate_right(self) -> Self {
 		match self {
 			Direction4::Up => Direction4::Right,
@@ -81,6 +99,13 @@ impl<T: AddAssign + SubAssign + PartialOrd + Copy> Vector2D<T> {
 	}
 }
 
+impl Vector2D<i16> {
+	/// Returns the number of orthogonal steps between two points.
+	pub fn manhattan_distance(self, other: Vector2D<i16>) -> u16 {
+		(self.x - other.x).unsigned_abs() + (self.y - other.y).unsigned_abs()
+	}
+}
+
 impl<T: Add<Output = T>> Add<Vector2D<T>> for Vector2D<T> {
 	type Output = Vector2D<T>;
 	fn add(self, rhs: Vector2D<T>) -> Self::Output {
@@ -148,3 +173,96 @@ impl<T: MulAssign + Copy> MulAssign<T> for Vector2D<T> {
 		self.y *= rhs;
 	}
 }
+
+/// What happens when something tries to move past the edge of a Bounds.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum Edge {
+	/// Stop at the edge.
+	#[default]
+	Clamp,
+	/// Continue from the opposite edge.
+	Wrap,
+}
+
+/// A rectangle of positions from (0, 0) up to but not including (width, height),
+/// such as the tiles of a map or the options of a menu.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Bounds {
+	pub width: u16,
+	pub height: u16,
+	pub edge: Edge,
+}
+
+impl Bounds {
+	pub const fn new(width: u16, height: u16) -> Self {
+		Self {
+			width,
+			height,
+			edge: Edge::Clamp,
+		}
+	}
+
+	pub const fn with_edge(self, edge: Edge) -> Self {
+		Self { edge, ..self }
+	}
+
+	/// Returns true if a position lies within the bounds.
+	pub fn contains(&self, position: Vector2D<i16>) -> bool {
+		position.x >= 0
+			&& position.y >= 0
+			&& position.x < self.width as i16
+			&& position.y < self.height as i16
+	}
+
+	/// Moves a position by `offset`, then clamps or wraps it back within the bounds.
+	/// Empty bounds have nowhere to move to, so the position is returned unchanged.
+	pub fn step(&self, position: Vector2D<i16>, offset: Vector2D<i16>) -> Vector2D<i16> {
+		if self.width == 0 || self.height == 0 {
+			return position;
+		}
+		let axis = |value: i16, size: u16| match self.edge {
+			Edge::Clamp => value.clamp(0, size as i16 - 1),
+			Edge::Wrap => value.rem_euclid(size as i16),
+		};
+		let position = position + offset;
+		Vector2D {
+			x: axis(position.x, self.width),
+			y: axis(position.y, self.height),
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn at(x: i16, y: i16) -> Vector2D<i16> {
+		Vector2D { x, y }
+	}
+
+	#[test]
+	fn clamped_bounds_stop_at_the_edge() {
+		let bounds = Bounds::new(4, 3);
+		assert_eq!(bounds.step(at(1, 1), at(1, 1)), at(2, 2));
+		assert_eq!(bounds.step(at(3, 0), at(1, 0)), at(3, 0));
+		assert_eq!(bounds.step(at(0, 0), at(0, -1)), at(0, 0));
+		assert_eq!(bounds.step(at(1, 2), at(5, 5)), at(3, 2));
+	}
+
+	#[test]
+	fn wrapping_bounds_continue_from_the_opposite_edge() {
+		let bounds = Bounds::new(4, 3).with_edge(Edge::Wrap);
+		assert_eq!(bounds.step(at(1, 1), at(1, 1)), at(2, 2));
+		assert_eq!(bounds.step(at(3, 0), at(1, 0)), at(0, 0));
+		assert_eq!(bounds.step(at(0, 0), at(0, -1)), at(0, 2));
+		assert_eq!(bounds.step(at(0, 0), at(-5, 0)), at(3, 0));
+	}
+
+	#[test]
+	fn empty_bounds_leave_the_position_alone() {
+		for bounds in [Bounds::new(0, 3), Bounds::new(3, 0).with_edge(Edge::Wrap)] {
+			assert_eq!(bounds.step(at(1, 1), at(1, 0)), at(1, 1));
+			assert!(!bounds.contains(at(0, 0)));
+		}
+	}
+}
@@ -0,0 +1,37 @@
+use crate::unit::Faction;
+
+/// Tracks whose phase it is and how many turns have passed.
+/// Each turn consists of a player phase, then an enemy phase, then an "other" phase.
+#[derive(Clone, Copy, Debug)]
+pub struct TurnState {
+	/// The current turn, starting at 1.
+	pub turn: u16,
+	pub phase: Faction,
+}
+
+impl Default for TurnState {
+	fn default() -> Self {
+		Self::new()
+	}
+}
+
+impl TurnState {
+	pub fn new() -> Self {
+		Self {
+			turn: 1,
+			phase: Faction::Player,
+		}
+	}
+
+	/// Moves on to the next phase, starting a new turn after the "other" phase.
+	pub fn advance(&mut self) {
+		self.phase = match self.phase {
+			Faction::Player => Faction::Enemy,
+			Faction::Enemy => Faction::Other,
+			Faction::Other => {
+				self.turn += 1;
+				Faction::Player
+			}
+		};
+	}
+}
@@ -0,0 +1,365 @@
+use crate::ai::Behavior;
+use crate::camera::{Camera, METATILE_SIZE};
+use crate::class::ClassId;
+use crate::combat::{Weapon, WeaponKind};
+use crate::item::{Inventory, ItemKind};
+use crate::console::*;
+use crate::experience::{self, LevelUp, EXP_PER_LEVEL, INITIAL_GROWTH_PROGRESS, MAX_LEVEL};
+use crate::game::UnitData;
+use crate::rng::Rng;
+use crate::stats::Stats;
+use crate::terrain::MovementClass;
+use crate::tools::Image;
+use crate::transform::Vector2D;
+use crate::video::{SpriteSize, Video};
+use core::fmt;
+
+/// The most units a single level may contain.
+/// Each unit needs an OAM entry, so this must leave room for the cursor and any menus.
+pub const MAX_UNITS: usize = 64;
+
+/// Determines which phase a unit acts in, and who it fights.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Faction {
+	Player,
+	Enemy,
+	/// Allied or neutral units, which act after the enemy.
+	Other,
+}
+
+impl Faction {
+	/// Returns true if units of these factions fight each other.
+	/// Player and "other" units are allied against the enemy.
+	pub fn is_hostile_to(self, other: Faction) -> bool {
+		(self == Faction::Enemy) != (other == Faction::Enemy)
+	}
+}
+
+/// Converts a 15-bit color to grey, preserving its brightness.
+fn greyscale(color: u16) -> u16 {
+	let r = color & 0x1F;
+	let g = (color >> 5) & 0x1F;
+	let b = (color >> 10) & 0x1F;
+	let luma = (r * 77 + g * 151 + b * 28) >> 8;
+	luma | luma << 5 | luma << 10
+}
+
+/// VRAM locations of a unit's map sprite, shared by every unit which uses it.
+#[derive(Clone, Copy, Default)]
+pub struct UnitSprite {
+	/// Each animation frame: idle, idle (alternate), selected, and selected (alternate).
+	pub frames: [u16; 4],
+	pub palette: u16,
+	/// A greyscale copy of `palette`, used once a unit has acted.
+	pub grey_palette: u16,
+}
+
+impl UnitSprite {
+	pub fn load<V: Video>(vram: &mut Vram<V>, image: &Image) -> Self {
+		let palette = image.palette();
+		let tile_id = vram.load_4bpp_obj_texture(image.tiles());
+		let mut grey = [0; 256];
+		let grey = &mut grey[..palette.len()];
+		for (grey, color) in grey.iter_mut().zip(palette) {
+			*grey = greyscale(*color);
+		}
+		Self {
+			frames: core::array::from_fn(|frame| tile_id + image.tile(frame)),
+			palette: vram.load_obj_palette(palette),
+			grey_palette: vram.load_obj_palette(grey),
+		}
+	}
+}
+
+#[derive(Clone)]
+pub struct Unit<'a> {
+	pub name: &'a str,
+	pub class: ClassId,
+	pub level: u8,
+	/// Experience towards the next level, from 0 to 99.
+	pub exp: u8,
+	pub is_boss: bool,
+	pub faction: Faction,
+	/// Set once the unit has acted this phase.
+	pub has_acted: bool,
+	pub position: Vector2D<i16>,
+	/// Movement points available each turn.
+	pub movement: u8,
+	pub movement_class: MovementClass,
+	pub stats: Stats,
+	/// Carries fixed growths over from one level to the next.
+	pub growth_progress: Stats,
+	/// Current hit points.
+	pub hp: u8,
+	pub inventory: Inventory,
+	pub behavior: Behavior,
+	/// Must be loaded by the scene before the unit is drawn.
+	pub sprite: UnitSprite,
+	animation_timer: u8,
+}
+
+impl<'a> Unit<'a> {
+	pub fn new(data: &UnitData<'a>) -> Self {
+		let class = data.class.data();
+		let stats = experience::average_stats(
+			&class.bases,
+			&class.growths,
+			&class.caps,
+			data.level.saturating_sub(1),
+		);
+		let mut unit = Self {
+			name: data.name,
+			class: data.class,
+			level: data.level,
+			exp: 0,
+			is_boss: data.is_boss,
+			faction: data.faction,
+			has_acted: false,
+			position: Vector2D {
+				x: data.x as i16,
+				y: data.y as i16,
+			},
+			movement: class.movement,
+			movement_class: class.movement_class,
+			stats,
+			growth_progress: INITIAL_GROWTH_PROGRESS,
+			hp: stats.hp,
+			inventory: Inventory::from_ids(data.items),
+			behavior: data.behavior,
+			sprite: UnitSprite::default(),
+			animation_timer: 0,
+		};
+		unit.equip_first_weapon();
+		unit
+	}
+
+	/// Returns true if the unit's class can wield an item as a weapon.
+	pub fn can_wield(&self, kind: ItemKind) -> bool {
+		match kind {
+			ItemKind::Weapon(kind) => self.class.data().can_wield(kind),
+			_ => false,
+		}
+	}
+
+	/// Returns the slot of the equipped weapon, if the unit has one.
+	/// Only the first slot may be equipped.
+	pub fn equipped_slot(&self) -> Option<usize> {
+		let item = self.inventory.get(0)?;
+		self.can_wield(item.data().kind).then_some(0)
+	}
+
+	pub fn equipped_weapon(&self) -> Option<Weapon> {
+		self.inventory.get(self.equipped_slot()?)?.data().weapon()
+	}
+
+	/// Equips an item, if the unit can wield it. Returns true on success.
+	pub fn equip(&mut self, slot: usize) -> bool {
+		match self.inventory.get(slot) {
+			Some(item) if self.can_wield(item.data().kind) => {
+				self.inventory.equip(slot);
+				true
+			}
+			_ => false,
+		}
+	}
+
+	/// Equips the first weapon in the inventory that the unit can wield.
+	pub fn equip_first_weapon(&mut self) {
+		let slot = self
+			.inventory
+			.iter()
+			.find(|(_, item)| self.can_wield(item.data().kind))
+			.map(|(slot, _)| slot);
+		if let Some(slot) = slot {
+			self.inventory.equip(slot);
+		}
+	}
+
+	/// Wears down the equipped weapon once per strike.
+	/// If it breaks, the next usable weapon is equipped in its place.
+	/// Returns true if the weapon broke.
+	pub fn wear_weapon(&mut self, strikes: usize) -> bool {
+		for _ in 0..strikes {
+			let Some(slot) = self.equipped_slot() else {
+				return false;
+			};
+			if self.inventory.spend(slot) {
+				self.equip_first_weapon();
+				return true;
+			}
+		}
+		false
+	}
+
+	/// Returns true if the unit can use an item as a staff.
+	pub fn can_use_staff(&self, kind: ItemKind) -> bool {
+		kind == ItemKind::Staff && self.class.data().can_wield(WeaponKind::Staff)
+	}
+
+	/// Restores HP, up to the unit's maximum.
+	pub fn heal(&mut self, amount: u8) {
+		self.hp = self.hp.saturating_add(amount).min(self.stats.hp);
+	}
+
+	/// Uses a consumable item on this unit, spending one of its uses.
+	/// Returns false if the item can't be used.
+	pub fn use_item(&mut self, slot: usize) -> bool {
+		let Some(item) = self.inventory.get(slot) else {
+			return false;
+		};
+		let data = item.data();
+		if data.kind != ItemKind::Consumable || self.hp >= self.stats.hp {
+			return false;
+		}
+		self.heal(data.heal);
+		self.inventory.spend(slot);
+		true
+	}
+
+	/// Awards experience, levelling up once it reaches EXP_PER_LEVEL.
+	/// Returns the results of the level up, if there was one.
+	pub fn gain_exp(&mut self, amount: u8, rng: &mut Rng) -> Option<LevelUp> {
+		if self.level >= MAX_LEVEL {
+			return None;
+		}
+
+		let total = self.exp as u16 + amount as u16;
+		if total < EXP_PER_LEVEL as u16 {
+			self.exp = total as u8;
+			return None;
+		}
+
+		self.level += 1;
+		self.exp = if self.level >= MAX_LEVEL { 0 } else { (total - EXP_PER_LEVEL as u16) as u8 };
+
+		let class = self.class.data();
+		let before = self.stats;
+		let gains = experience::grow(
+			&mut self.stats,
+			&mut self.growth_progress,
+			&class.growths,
+			&class.caps,
+			rng,
+		);
+		self.hp += gains.hp;
+
+		Some(LevelUp {
+			level: self.level,
+			before,
+			gains,
+		})
+	}
+
+	/// Draws the unit relative to the camera. Units which are off screen are skipped,
+	/// but keep animating so that they stay in step with everyone else.
+	pub fn draw(&mut self, oam: &mut Oam, selected: bool, camera: &Camera) {
+		let animation_timer = self.animation_timer;
+		self.animation_timer = self.animation_timer.wrapping_add(1);
+		let Some(position) = camera.to_screen(self.position * METATILE_SIZE, Vector2D { x: 16, y: 16 }) else {
+			return;
+		};
+
+		let sprite = oam.reserve_entry();
+		sprite.x = position.x;
+		sprite.y = position.y;
+		sprite.size = SpriteSize::S16x16;
+		sprite.tile_id = self.sprite.frames[
+			if selected {2} else {0}
+			+ if animation_timer & 0x10 != 0 && !self.has_acted {1} else {0}
+		];
+		sprite.palbank = if self.has_acted {
+			self.sprite.grey_palette
+		} else {
+			self.sprite.palette
+		};
+	}
+}
+
+/// Returned when a unit is added to a full UnitList.
+#[derive(Debug)]
+pub struct RosterFull;
+
+impl fmt::Display for RosterFull {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		write!(f, "cannot hold more than {MAX_UNITS} units")
+	}
+}
+
+/// A fixed-capacity collection of units.
+/// Each unit keeps the same index for as long as it is in the list, even if others are removed.
+pub struct UnitList<'a> {
+	units: [Option<Unit<'a>>; MAX_UNITS],
+}
+
+impl Default for UnitList<'_> {
+	fn default() -> Self {
+		Self::new()
+	}
+}
+
+impl<'a> UnitList<'a> {
+	pub const fn new() -> Self {
+		Self {
+			units: [const { None }; MAX_UNITS],
+		}
+	}
+
+	/// Adds a unit to the first free slot and returns its index.
+	pub fn push(&mut self, unit: Unit<'a>) -> Result<usize, RosterFull> {
+		let (i, slot) = self
+			.units
+			.iter_mut()
+			.enumerate()
+			.find(|(_, slot)| slot.is_none())
+			.ok_or(RosterFull)?;
+		*slot = Some(unit);
+		Ok(i)
+	}
+
+	pub fn remove(&mut self, i: usize) -> Option<Unit<'a>> {
+		self.units.get_mut(i)?.take()
+	}
+
+	/// Removes every unit.
+	pub fn clear(&mut self) {
+		self.units = [const { None }; MAX_UNITS];
+	}
+
+	pub fn get(&self, i: usize) -> Option<&Unit<'a>> {
+		self.units.get(i)?.as_ref()
+	}
+
+	pub fn get_mut(&mut self, i: usize) -> Option<&mut Unit<'a>> {
+		self.units.get_mut(i)?.as_mut()
+	}
+
+	pub fn len(&self) -> usize {
+		self.units.iter().filter(|slot| slot.is_some()).count()
+	}
+
+	pub fn is_empty(&self) -> bool {
+		self.units.iter().all(|slot| slot.is_none())
+	}
+
+	/// Iterates over each unit along with its index.
+	pub fn iter(&self) -> impl Iterator<Item = (usize, &Unit<'a>)> + '_ {
+		self.units
+			.iter()
+			.enumerate()
+			.filter_map(|(i, slot)| Some((i, slot.as_ref()?)))
+	}
+
+	pub fn iter_mut(&mut self) -> impl Iterator<Item = (usize, &mut Unit<'a>)> + '_ {
+		self.units
+			.iter_mut()
+			.enumerate()
+			.filter_map(|(i, slot)| Some((i, slot.as_mut()?)))
+	}
+
+	/// Returns the index of the unit standing at a position.
+	pub fn at(&self, position: Vector2D<i16>) -> Option<usize> {
+		self.iter()
+			.find(|(_, unit)| unit.position == position)
+			.map(|(i, _)| i)
+	}
+}
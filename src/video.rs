@@ -0,0 +1,116 @@
+/// One entry of a text-mode tilemap: which tile to draw, and how.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub struct TileEntry {
+	pub tile: u16,
+	pub palbank: u16,
+	pub hflip: bool,
+	pub vflip: bool,
+}
+
+impl TileEntry {
+	/// Tile 0 is always left blank, so this draws nothing.
+	pub const BLANK: TileEntry = TileEntry {
+		tile: 0,
+		palbank: 0,
+		hflip: false,
+		vflip: false,
+	};
+
+	pub const fn new(tile: u16, palbank: u16) -> Self {
+		Self {
+			tile,
+			palbank,
+			hflip: false,
+			vflip: false,
+		}
+	}
+
+	/// Packs the entry into the hardware's screenblock format.
+	pub const fn to_bits(self) -> u16 {
+		(self.tile & 0x3FF)
+			| (self.hflip as u16) << 10
+			| (self.vflip as u16) << 11
+			| (self.palbank & 0xF) << 12
+	}
+
+	pub const fn from_bits(bits: u16) -> Self {
+		Self {
+			tile: bits & 0x3FF,
+			palbank: bits >> 12,
+			hflip: bits & 1 << 10 != 0,
+			vflip: bits & 1 << 11 != 0,
+		}
+	}
+}
+
+/// Square sprite sizes. Only square sprites are used so far.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum SpriteSize {
+	#[default]
+	S8x8,
+	S16x16,
+	S32x32,
+	S64x64,
+}
+
+impl SpriteSize {
+	/// Width and height, in pixels.
+	pub const fn pixels(self) -> i16 {
+		match self {
+			SpriteSize::S8x8 => 8,
+			SpriteSize::S16x16 => 16,
+			SpriteSize::S32x32 => 32,
+			SpriteSize::S64x64 => 64,
+		}
+	}
+}
+
+/// A 4bpp sprite, positioned in screen space.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub struct Sprite {
+	pub x: i16,
+	pub y: i16,
+	pub tile_id: u16,
+	pub palbank: u16,
+	pub size: SpriteSize,
+	pub hflip: bool,
+	pub vflip: bool,
+}
+
+/// Everything the game needs from the PPU.
+///
+/// Game logic only ever draws through this trait, so it can run against real hardware,
+/// a software renderer, or nothing at all. Implementations are expected to be cheap handles
+/// (a unit struct on the GBA), which is why they are passed around by copy.
+pub trait Video: Copy {
+	/// Copies 4bpp tiles into background tile memory, starting at tile `index`.
+	fn load_bg_tiles(&self, index: usize, data: &[u32]);
+	/// Copies 4bpp tiles into sprite tile memory, starting at tile `index`.
+	fn load_obj_tiles(&self, index: usize, data: &[u32]);
+	/// Copies colors into background palette memory, starting at color `index`.
+	fn load_bg_colors(&self, index: usize, colors: &[u16]);
+	/// Copies colors into sprite palette memory, starting at color `index`.
+	fn load_obj_colors(&self, index: usize, colors: &[u16]);
+	fn set_tile(&self, screenblock: usize, row: usize, col: usize, entry: TileEntry);
+	/// Scrolls a background layer so that (x, y) is at the top-left of the screen.
+	fn set_scroll(&self, background: usize, x: u16, y: u16);
+	/// Shows a sprite in an OAM slot, or hides the slot.
+	fn set_sprite(&self, index: usize, sprite: Option<Sprite>);
+	/// Blanks the whole display, so that graphics can be changed without showing anything half-loaded.
+	fn set_forced_blank(&self, blank: bool);
+}
+
+/// Draws nothing. Useful for running the game logic on its own.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub struct NullVideo;
+
+impl Video for NullVideo {
+	fn load_bg_tiles(&self, _index: usize, _data: &[u32]) {}
+	fn load_obj_tiles(&self, _index: usize, _data: &[u32]) {}
+	fn load_bg_colors(&self, _index: usize, _colors: &[u16]) {}
+	fn load_obj_colors(&self, _index: usize, _colors: &[u16]) {}
+	fn set_tile(&self, _screenblock: usize, _row: usize, _col: usize, _entry: TileEntry) {}
+	fn set_scroll(&self, _background: usize, _x: u16, _y: u16) {}
+	fn set_sprite(&self, _index: usize, _sprite: Option<Sprite>) {}
+	fn set_forced_blank(&self, _blank: bool) {}
+}
@@ -0,0 +1,62 @@
+use furry_emblem::ai::Behavior;
+use furry_emblem::campaign::{appeared_before, CHAPTERS};
+use furry_emblem::class::ClassId;
+use furry_emblem::game::{GameState, UnitData};
+use furry_emblem::transform::Vector2D;
+use furry_emblem::unit::{Faction, Unit, UnitList};
+use furry_emblem::video::NullVideo;
+
+/// Begins the chapter after the prologue with a roster.
+fn next_chapter(roster: &UnitList<'static>) -> GameState<'static, NullVideo> {
+	let next = CHAPTERS[0].next.expect("the prologue should lead somewhere");
+	GameState::with_roster(NullVideo, CHAPTERS[next as usize].map, roster, |name| appeared_before(next, name)).unwrap()
+}
+
+fn find<'g>(game: &'g GameState<'static, NullVideo>, name: &str) -> Option<&'g Unit<'static>> {
+	game.units().iter().map(|(_, unit)| unit).find(|unit| unit.name == name)
+}
+
+#[test]
+fn survivors_carry_over_into_the_next_chapter() {
+	let prologue = GameState::new(NullVideo, CHAPTERS[0].map).unwrap();
+	let mut roster = UnitList::new();
+	prologue.survivors(&mut roster);
+	assert!(roster.iter().all(|(_, unit)| unit.faction == Faction::Player));
+	let (luvui, _) = roster.iter().find(|(_, unit)| unit.name == "Luvui").unwrap();
+	roster.get_mut(luvui).unwrap().level = 5;
+
+	let game = next_chapter(&roster);
+	let luvui = find(&game, "Luvui").unwrap();
+	assert_eq!(luvui.level, 5);
+	assert_eq!(luvui.position, Vector2D { x: 1, y: 4 });
+	// Units who join in this chapter are there too.
+	assert!(find(&game, "Brom").is_some());
+}
+
+#[test]
+fn fallen_units_do_not_return() {
+	let game = next_chapter(&UnitList::new());
+	assert!(find(&game, "Luvui").is_none());
+	assert!(find(&game, "Brom").is_some());
+}
+
+#[test]
+fn other_units_are_placed_on_spawn_tiles() {
+	let mut roster = UnitList::new();
+	roster
+		.push(Unit::new(&UnitData {
+			name: "Extra",
+			class: ClassId::Fighter,
+			x: 0,
+			y: 0,
+			is_boss: false,
+			level: 1,
+			faction: Faction::Player,
+			items: &[],
+			behavior: Behavior::Aggressive,
+		}))
+		.unwrap();
+
+	let game = next_chapter(&roster);
+	assert_eq!(find(&game, "Extra").unwrap().position, Vector2D { x: 0, y: 5 });
+}
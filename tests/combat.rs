@@ -0,0 +1,251 @@
+use furry_emblem::combat::*;
+use furry_emblem::stats::Stats;
+use furry_emblem::terrain::Terrain;
+
+const PLAINS: Terrain = Terrain {
+	name: "Plains",
+	movement_cost: [Some(1); 4],
+	defense: 0,
+	avoid: 0,
+	heal: 0,
+};
+
+/// A plain fighter: every stat is 5 except luck, which is 0 so that it doesn't skew hit and crit rates.
+const STATS: Stats = Stats {
+	luck: 0,
+	..Stats::splat(5)
+};
+
+fn weapon(kind: WeaponKind) -> Weapon {
+	Weapon {
+		kind,
+		might: 5,
+		hit: 80,
+		crit: 0,
+		weight: 0,
+		min_range: 1,
+		max_range: 1,
+		magical: false,
+	}
+}
+
+fn fighter(stats: &Stats, weapon: Option<Weapon>) -> Combatant<'_> {
+	Combatant {
+		stats,
+		hp: stats.hp,
+		weapon,
+		terrain: &PLAINS,
+	}
+}
+
+/// Every strike lands, and each is critical if `crit` is set.
+struct Always {
+	crit: bool,
+}
+
+impl Rolls for Always {
+	fn hit(&mut self, _chance: u8) -> bool {
+		true
+	}
+
+	fn crit(&mut self, _chance: u8) -> bool {
+		self.crit
+	}
+}
+
+#[test]
+fn evenly_matched_units_trade_single_blows() {
+	let sword = fighter(&STATS, Some(weapon(WeaponKind::Sword)));
+	let forecast = forecast(&sword, &sword, 1);
+
+	// 5 strength + 5 might - 5 defense.
+	assert_eq!(forecast.attacker.damage, 5);
+	// 80 hit + 5 skill * 2, against 5 speed * 2.
+	assert_eq!(forecast.attacker.hit, 80);
+	assert_eq!(forecast.attacker.crit, 2);
+	assert_eq!(forecast.attacker.attacks, 1);
+	assert_eq!(forecast.attacker, forecast.defender);
+}
+
+#[test]
+fn hit_and_crit_rates_are_clamped() {
+	let sharp = Weapon {
+		hit: 200,
+		crit: 150,
+		..weapon(WeaponKind::Sword)
+	};
+	let blunt = Weapon {
+		hit: 0,
+		..weapon(WeaponKind::Sword)
+	};
+	let sword = Some(weapon(WeaponKind::Sword));
+
+	let forecast_sharp = forecast(&fighter(&STATS, Some(sharp)), &fighter(&STATS, sword), 1);
+	assert_eq!(forecast_sharp.attacker.hit, 100);
+	assert_eq!(forecast_sharp.attacker.crit, 100);
+
+	let nimble = Stats {
+		speed: 60,
+		luck: 30,
+		..STATS
+	};
+	let forecast_blunt = forecast(&fighter(&STATS, Some(blunt)), &fighter(&nimble, sword), 1);
+	assert_eq!(forecast_blunt.attacker.hit, 0);
+	assert_eq!(forecast_blunt.attacker.crit, 0);
+}
+
+#[test]
+fn luck_reduces_crit() {
+	let keen = Weapon {
+		crit: 10,
+		..weapon(WeaponKind::Sword)
+	};
+	let lucky = Stats { luck: 4, ..STATS };
+
+	let forecast = forecast(&fighter(&STATS, Some(keen)), &fighter(&lucky, Some(keen)), 1);
+	// 10 crit + 5 skill / 2 - 4 luck.
+	assert_eq!(forecast.attacker.crit, 8);
+	// 10 crit + 5 skill / 2 - 0 luck.
+	assert_eq!(forecast.defender.crit, 12);
+}
+
+#[test]
+fn protection_above_attack_deals_no_damage() {
+	let armored = Stats { defense: 30, ..STATS };
+	let forecast = forecast(
+		&fighter(&STATS, Some(weapon(WeaponKind::Sword))),
+		&fighter(&armored, Some(weapon(WeaponKind::Sword))),
+		1,
+	);
+	assert_eq!(forecast.attacker.damage, 0);
+}
+
+#[test]
+fn follow_ups_need_enough_speed() {
+	let sword = Some(weapon(WeaponKind::Sword));
+	let just_fast_enough = Stats {
+		speed: STATS.speed + FOLLOW_UP_SPEED as u8,
+		..STATS
+	};
+	let not_quite = Stats {
+		speed: STATS.speed + FOLLOW_UP_SPEED as u8 - 1,
+		..STATS
+	};
+
+	let forecast_fast = forecast(&fighter(&just_fast_enough, sword), &fighter(&STATS, sword), 1);
+	assert_eq!(forecast_fast.attacker.attacks, 2);
+	assert_eq!(forecast_fast.defender.attacks, 1);
+
+	let forecast_slow = forecast(&fighter(&not_quite, sword), &fighter(&STATS, sword), 1);
+	assert_eq!(forecast_slow.attacker.attacks, 1);
+}
+
+#[test]
+fn heavy_weapons_slow_weak_units_down() {
+	let heavy = Weapon {
+		weight: 5 + FOLLOW_UP_SPEED as u8,
+		..weapon(WeaponKind::Axe)
+	};
+	let sword = Some(weapon(WeaponKind::Sword));
+
+	// Weight beyond strength costs speed, so the sword user follows up.
+	let forecast = forecast(&fighter(&STATS, Some(heavy)), &fighter(&STATS, sword), 1);
+	assert_eq!(forecast.attacker.attacks, 1);
+	assert_eq!(forecast.defender.attacks, 2);
+}
+
+#[test]
+fn out_of_range_defenders_cannot_counter() {
+	let bow = Weapon {
+		min_range: 2,
+		max_range: 2,
+		..weapon(WeaponKind::Bow)
+	};
+	let forecast = forecast(
+		&fighter(&STATS, Some(bow)),
+		&fighter(&STATS, Some(weapon(WeaponKind::Sword))),
+		2,
+	);
+	assert_eq!(forecast.attacker.attacks, 1);
+	assert_eq!(forecast.defender.attacks, 0);
+	assert_eq!(forecast.defender.damage, 0);
+}
+
+#[test]
+fn the_weapon_triangle_shifts_hit_and_damage() {
+	let neutral = forecast(
+		&fighter(&STATS, Some(weapon(WeaponKind::Sword))),
+		&fighter(&STATS, Some(weapon(WeaponKind::Sword))),
+		1,
+	);
+
+	for (strong, weak) in [
+		(WeaponKind::Sword, WeaponKind::Axe),
+		(WeaponKind::Axe, WeaponKind::Lance),
+		(WeaponKind::Lance, WeaponKind::Sword),
+	] {
+		let forecast = forecast(
+			&fighter(&STATS, Some(weapon(strong))),
+			&fighter(&STATS, Some(weapon(weak))),
+			1,
+		);
+		let advantage = forecast.attacker;
+		let disadvantage = forecast.defender;
+
+		assert_eq!(advantage.hit as i16, neutral.attacker.hit as i16 + TRIANGLE_HIT, "{strong:?} against {weak:?}");
+		assert_eq!(advantage.damage as i16, neutral.attacker.damage as i16 + TRIANGLE_DAMAGE);
+		assert_eq!(disadvantage.hit as i16, neutral.defender.hit as i16 - TRIANGLE_HIT);
+		assert_eq!(disadvantage.damage as i16, neutral.defender.damage as i16 - TRIANGLE_DAMAGE);
+	}
+
+	// Weapons outside the triangle are unaffected.
+	let forecast = forecast(
+		&fighter(&STATS, Some(weapon(WeaponKind::Bow))),
+		&fighter(&STATS, Some(weapon(WeaponKind::Axe))),
+		1,
+	);
+	assert_eq!(forecast.attacker, neutral.attacker);
+	assert_eq!(forecast.defender, neutral.defender);
+}
+
+#[test]
+fn strikes_alternate_and_the_faster_side_follows_up() {
+	let sturdy = Stats { hp: 12, ..STATS };
+	let fast = Stats { speed: 20, ..sturdy };
+	let sword = Some(weapon(WeaponKind::Sword));
+	let forecast = forecast(&fighter(&fast, sword), &fighter(&sturdy, sword), 1);
+
+	let result = resolve(&forecast, &mut Always { crit: false });
+	let sides: Vec<Side> = result.strikes().iter().map(|strike| strike.side).collect();
+	assert_eq!(sides, [Side::Attacker, Side::Defender, Side::Attacker]);
+	assert_eq!(result.attacker_hp, 12 - 5);
+	assert_eq!(result.defender_hp, 12 - 5 * 2);
+	assert!(result.strikes().iter().all(|strike| !strike.lethal));
+}
+
+#[test]
+fn crits_multiply_damage() {
+	let sword = Some(weapon(WeaponKind::Sword));
+	let sturdy = Stats { hp: 50, ..STATS };
+	let forecast = forecast(&fighter(&sturdy, sword), &fighter(&sturdy, sword), 1);
+
+	let result = resolve(&forecast, &mut Always { crit: true });
+	let first = result.strikes()[0];
+	assert!(first.hit && first.crit);
+	assert_eq!(first.damage, forecast.attacker.damage * CRIT_MULTIPLIER);
+	assert_eq!(result.defender_hp, 50 - first.damage);
+}
+
+#[test]
+fn a_lethal_first_strike_cancels_the_counter() {
+	let frail = Stats { hp: 5, ..STATS };
+	let sword = Some(weapon(WeaponKind::Sword));
+	let forecast = forecast(&fighter(&STATS, sword), &fighter(&frail, sword), 1);
+	assert_eq!(forecast.defender.attacks, 1);
+
+	let result = resolve(&forecast, &mut Always { crit: false });
+	assert_eq!(result.strikes().len(), 1);
+	assert!(result.strikes()[0].lethal);
+	assert_eq!(result.defender_hp, 0);
+	assert_eq!(result.attacker_hp, STATS.hp);
+}
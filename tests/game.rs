@@ -0,0 +1,306 @@
+use furry_emblem::ai::{self, Behavior};
+use furry_emblem::assets::maps::DEBUG_MAP;
+use furry_emblem::class::ClassId;
+use furry_emblem::console::{Input, KeySource, Keys, Vram};
+use furry_emblem::game::{GameState, LevelData, UnitData};
+use furry_emblem::item::ItemId;
+use furry_emblem::objective::{Defeat, Objective};
+use furry_emblem::scene::Scene;
+use furry_emblem::transform::Vector2D;
+use furry_emblem::unit::{Faction, Unit, UnitList};
+use furry_emblem::video::NullVideo;
+
+/// Presses each key for one frame, then lets go of it for one frame.
+struct Script {
+	frames: Vec<Keys>,
+}
+
+impl Script {
+	fn new(presses: &[Keys]) -> Self {
+		let mut frames: Vec<Keys> = presses.iter().flat_map(|keys| [*keys, Keys::NONE]).collect();
+		frames.reverse();
+		Self { frames }
+	}
+
+	fn is_done(&self) -> bool {
+		self.frames.is_empty()
+	}
+}
+
+impl KeySource for Script {
+	fn read(&mut self) -> Keys {
+		self.frames.pop().unwrap_or(Keys::NONE)
+	}
+}
+
+fn start() -> GameState<'static, NullVideo> {
+	let mut game = GameState::new(NullVideo, &DEBUG_MAP).unwrap();
+	game.enter(&mut Vram::new(NullVideo));
+	game
+}
+
+/// Runs the game until the script is finished.
+fn play(game: &mut GameState<'static, NullVideo>, presses: &[Keys]) {
+	let mut input = Input::new();
+	let mut script = Script::new(presses);
+	while !script.is_done() {
+		input.update(&mut script);
+		game.tick(&input);
+	}
+}
+
+/// Runs the game without any input until it is the player's phase again.
+fn wait_for_player(game: &mut GameState<'static, NullVideo>) {
+	let mut input = Input::new();
+	let mut script = Script::new(&[]);
+	for _ in 0..10_000 {
+		if game.turn().phase == Faction::Player {
+			return;
+		}
+		input.update(&mut script);
+		game.tick(&input);
+	}
+	panic!("the computer never finished its phase");
+}
+
+#[test]
+fn cursor_stays_on_the_map() {
+	let mut game = start();
+	play(&mut game, &[Keys::UP, Keys::LEFT]);
+	assert_eq!(game.cursor_position(), Vector2D { x: 0, y: 0 });
+	play(&mut game, &[Keys::RIGHT, Keys::RIGHT, Keys::DOWN]);
+	assert_eq!(game.cursor_position(), Vector2D { x: 2, y: 1 });
+}
+
+#[test]
+fn waiting_ends_the_phase() {
+	let mut game = start();
+	// Luvui starts at (1, 2).
+	play(&mut game, &[Keys::RIGHT, Keys::DOWN, Keys::DOWN, Keys::A]);
+	// Move one tile right, then choose Wait from [Item, Wait].
+	play(&mut game, &[Keys::RIGHT, Keys::A, Keys::DOWN, Keys::A]);
+
+	let luvui = game.units().at(Vector2D { x: 2, y: 2 }).unwrap();
+	assert_eq!(game.units().get(luvui).unwrap().name, "Luvui");
+	assert_eq!(game.turn().phase, Faction::Enemy);
+
+	wait_for_player(&mut game);
+	assert_eq!(game.turn().turn, 2);
+	assert!(!game.units().get(luvui).unwrap().has_acted);
+}
+
+#[test]
+fn start_ends_the_phase() {
+	let mut game = start();
+	play(&mut game, &[Keys::START]);
+	assert_eq!(game.turn().phase, Faction::Enemy);
+	wait_for_player(&mut game);
+	assert_eq!(game.turn().turn, 2);
+}
+
+fn soldier(name: &'static str, faction: Faction, x: u16) -> UnitData<'static> {
+	UnitData {
+		name,
+		class: ClassId::Fighter,
+		x,
+		y: 0,
+		is_boss: false,
+		level: 1,
+		faction,
+		items: &[],
+		behavior: Behavior::Aggressive,
+	}
+}
+
+#[test]
+fn enemies_block_movement_but_allies_do_not() {
+	let units = [
+		soldier("Walker", Faction::Player, 0),
+		soldier("Ally", Faction::Other, 1),
+		soldier("Enemy", Faction::Enemy, 3),
+	];
+	let corridor = LevelData {
+		width: 8,
+		height: 1,
+		map: &[0; 8],
+		units: &units,
+		spawns: &[],
+		objective: Objective::Rout,
+		defeat: Defeat { lord: None, turn_limit: None },
+	};
+	let mut list = UnitList::new();
+	for data in &units {
+		list.push(Unit::new(data)).unwrap();
+	}
+
+	let walker = list.get(0).unwrap();
+	let reachable = corridor.movement_range(walker, &list);
+	assert!(reachable.contains(Vector2D { x: 2, y: 0 }));
+	assert!(!reachable.contains(Vector2D { x: 3, y: 0 }));
+	assert!(!reachable.contains(Vector2D { x: 4, y: 0 }));
+
+	list.remove(2);
+	let reachable = corridor.movement_range(list.get(0).unwrap(), &list);
+	assert!(reachable.contains(Vector2D { x: 5, y: 0 }));
+}
+
+/// The tileset index of a fort.
+const FORT: u8 = 2;
+
+#[test]
+fn hurt_retreating_units_flee_to_a_fort_and_heal() {
+	let units = [
+		soldier("Hero", Faction::Player, 0),
+		UnitData {
+			items: &[ItemId::IronAxe, ItemId::Vulnerary],
+			behavior: Behavior::Retreat,
+			..soldier("Coward", Faction::Enemy, 2)
+		},
+	];
+	let level = LevelData {
+		width: 8,
+		height: 1,
+		map: &[0, 0, 0, 0, FORT, 0, 0, 0],
+		units: &units,
+		spawns: &[],
+		objective: Objective::Rout,
+		defeat: Defeat { lord: None, turn_limit: None },
+	};
+	let mut list = UnitList::new();
+	for data in &units {
+		list.push(Unit::new(data)).unwrap();
+	}
+
+	// Healthy units still fight.
+	let decision = ai::decide(&level, &list, 1).unwrap();
+	assert_eq!(decision.item, None);
+
+	list.get_mut(1).unwrap().hp = 1;
+	let decision = ai::decide(&level, &list, 1).unwrap();
+	assert_eq!(decision.destination, Vector2D { x: 4, y: 0 });
+	assert_eq!(decision.target, None);
+	assert_eq!(decision.item, Some(1));
+}
+
+static FORT_UNITS: [UnitData; 2] = [
+	UnitData {
+		name: "Hero",
+		class: ClassId::Fighter,
+		x: 0,
+		y: 0,
+		is_boss: false,
+		level: 1,
+		faction: Faction::Player,
+		items: &[],
+		behavior: Behavior::Aggressive,
+	},
+	UnitData {
+		name: "Sentry",
+		class: ClassId::Fighter,
+		x: 3,
+		y: 0,
+		is_boss: false,
+		level: 1,
+		faction: Faction::Enemy,
+		items: &[],
+		behavior: Behavior::Guard,
+	},
+];
+
+static FORT_LEVEL: LevelData = LevelData {
+	width: 4,
+	height: 1,
+	map: &[FORT, 0, 0, 0],
+	units: &FORT_UNITS,
+	spawns: &[],
+	objective: Objective::Rout,
+	defeat: Defeat { lord: None, turn_limit: None },
+};
+
+#[test]
+fn forts_heal_units_at_the_start_of_their_phase() {
+	let mut hero = Unit::new(&FORT_UNITS[0]);
+	hero.hp = 1;
+	let max_hp = hero.stats.hp;
+	let mut roster = UnitList::new();
+	roster.push(hero).unwrap();
+
+	let mut game = GameState::with_roster(NullVideo, &FORT_LEVEL, &roster, |_| false).unwrap();
+	game.enter(&mut Vram::new(NullVideo));
+	play(&mut game, &[Keys::START]);
+	// Only the enemy phase has started, so the hero hasn't healed yet.
+	assert_eq!(game.units().get(0).unwrap().hp, 1);
+
+	wait_for_player(&mut game);
+	assert_eq!(game.units().get(0).unwrap().hp, 1 + max_hp / 5);
+}
+
+static TRADE_UNITS: [UnitData; 3] = [
+	UnitData {
+		name: "Hero",
+		class: ClassId::Fighter,
+		x: 0,
+		y: 0,
+		is_boss: false,
+		level: 1,
+		faction: Faction::Player,
+		items: &[ItemId::IronAxe],
+		behavior: Behavior::Aggressive,
+	},
+	UnitData {
+		name: "Friend",
+		class: ClassId::Fighter,
+		x: 1,
+		y: 0,
+		is_boss: false,
+		level: 1,
+		faction: Faction::Player,
+		items: &[ItemId::Vulnerary],
+		behavior: Behavior::Aggressive,
+	},
+	UnitData {
+		name: "Sentry",
+		class: ClassId::Fighter,
+		x: 7,
+		y: 0,
+		is_boss: false,
+		level: 1,
+		faction: Faction::Enemy,
+		items: &[],
+		behavior: Behavior::Guard,
+	},
+];
+
+static TRADE_LEVEL: LevelData = LevelData {
+	width: 8,
+	height: 1,
+	map: &[0; 8],
+	units: &TRADE_UNITS,
+	spawns: &[],
+	objective: Objective::Rout,
+	defeat: Defeat { lord: None, turn_limit: None },
+};
+
+fn items(game: &GameState<NullVideo>, unit: usize) -> Vec<ItemId> {
+	game.units().get(unit).unwrap().inventory.iter().map(|(_, item)| item.id).collect()
+}
+
+#[test]
+fn trades_go_both_ways() {
+	let mut game = GameState::new(NullVideo, &TRADE_LEVEL).unwrap();
+	game.enter(&mut Vram::new(NullVideo));
+	// Select the hero and stay put, then choose Trade from [Item, Trade, Wait] and the only partner.
+	play(&mut game, &[Keys::A, Keys::A, Keys::DOWN, Keys::A, Keys::A]);
+
+	// Right shows the partner's items; taking one moves it over.
+	play(&mut game, &[Keys::RIGHT, Keys::A]);
+	assert_eq!(items(&game, 0), [ItemId::IronAxe, ItemId::Vulnerary]);
+	assert_eq!(items(&game, 1), []);
+
+	// Left goes back to the hero's own items, to give one away.
+	play(&mut game, &[Keys::LEFT, Keys::A]);
+	assert_eq!(items(&game, 0), [ItemId::Vulnerary]);
+	assert_eq!(items(&game, 1), [ItemId::IronAxe]);
+	// The partner equips the weapon it was handed.
+	assert!(game.units().get(1).unwrap().equipped_weapon().is_some());
+}
@@ -0,0 +1,107 @@
+// Only the build script generates code from maps, so much of the module goes unused here.
+#[allow(dead_code)]
+#[path = "../build/maps.rs"]
+mod maps;
+
+use maps::{MapFile, MAX_MAP_HEIGHT, MAX_MAP_WIDTH};
+
+fn classes() -> Vec<String> {
+	vec!["Lord".to_string()]
+}
+
+/// Parses and validates a map, returning its errors one per line.
+fn errors(source: &str) -> Vec<String> {
+	let map = MapFile::parse("test.toml".to_string(), source.to_string()).unwrap();
+	match map.validate(4, &classes(), &[]) {
+		Ok(()) => Vec::new(),
+		Err(err) => err.to_string().lines().map(str::to_string).collect(),
+	}
+}
+
+/// A `width` by `height` map of plains.
+fn plains(width: u16, height: u16) -> String {
+	let tiles = vec!["0"; width as usize * height as usize].join(", ");
+	format!("width = {width}\nheight = {height}\ndata = [{tiles}]\n")
+}
+
+#[test]
+fn valid_maps_have_no_errors() {
+	let map = plains(3, 2)
+		+ "units = [{ name = \"Luvui\", class = \"Lord\", x = 0, y = 0 }]\n\
+		spawns = [{ x = 1, y = 0 }]\n\
+		[defeat]\n\
+		lord = \"Luvui\"\n";
+	assert_eq!(errors(&map), Vec::<String>::new());
+}
+
+#[test]
+fn oversized_maps_are_rejected() {
+	let wide = plains(MAX_MAP_WIDTH + 1, 1);
+	assert_eq!(
+		errors(&wide),
+		[format!("test.toml:1: the map is {} tiles wide, but can be at most {MAX_MAP_WIDTH}", MAX_MAP_WIDTH + 1)]
+	);
+
+	let high = plains(1, MAX_MAP_HEIGHT + 1);
+	assert_eq!(
+		errors(&high),
+		[format!("test.toml:2: the map is {} tiles high, but can be at most {MAX_MAP_HEIGHT}", MAX_MAP_HEIGHT + 1)]
+	);
+
+	assert_eq!(errors(&plains(MAX_MAP_WIDTH, MAX_MAP_HEIGHT)), Vec::<String>::new());
+}
+
+#[test]
+fn data_must_fill_the_map() {
+	let map = "width = 2\nheight = 2\ndata = [\n\t0, 0,\n\t0, 9,\n\t0,\n]\n";
+	assert_eq!(
+		errors(map),
+		[
+			"test.toml:4: data has 5 tiles, but a 2x2 map needs 4",
+			"test.toml:5: tile 9 at (1, 1) is not in the tileset, which has 4 tiles",
+		]
+	);
+}
+
+#[test]
+fn spawns_must_be_free_tiles_on_the_map() {
+	let map = plains(3, 3)
+		+ "units = [{ name = \"Luvui\", class = \"Lord\", x = 1, y = 1 }]\n\
+		spawns = [\n\
+			{ x = 0, y = 0 },\n\
+			{ x = 3, y = 0 },\n\
+			{ x = 0, y = 0 },\n\
+			{ x = 1, y = 1 },\n\
+		]\n";
+	assert_eq!(
+		errors(&map),
+		[
+			"test.toml:7: spawn (3, 0) is outside of the map",
+			"test.toml:8: spawn (0, 0) is listed twice",
+			"test.toml:9: spawn (1, 1) is taken by \"Luvui\"",
+		]
+	);
+}
+
+#[test]
+fn units_and_spawns_written_as_tables_report_their_own_lines() {
+	// Both units have the same name, which searching the text for it couldn't tell apart.
+	let map = plains(2, 2)
+		+ "\n[[units]]\nname = \"Lu\"\nclass = \"Lord\"\nx = 0\ny = 0\n\
+		\n[[units]]\nname = \"Lu\"\nclass = \"Lord\"\nx = 9\ny = 0\n\
+		\n[[spawns]]\nx = 1\ny = 1\n\
+		\n[[spawns]]\nx = 1\ny = 1\n";
+	assert_eq!(
+		errors(&map),
+		[
+			"test.toml:12: unit \"Lu\" at (9, 0) is outside of the map",
+			"test.toml:22: spawn (1, 1) is listed twice",
+		]
+	);
+}
+
+#[test]
+fn objective_errors_name_the_file_without_a_line() {
+	let map = plains(2, 2) + "[objective]\ntype = \"seize\"\nx = 5\ny = 0\n";
+	assert_eq!(errors(&map), ["test.toml: objective tile (5, 0) is outside of the map"]);
+}
@@ -0,0 +1,84 @@
+use furry_emblem::assets::maps::DEBUG_MAP;
+use furry_emblem::console::{Oam, Vram};
+use furry_emblem::game::GameState;
+use furry_emblem::ppu::{Frame, SoftwarePpu};
+use furry_emblem::scene::Scene;
+use furry_emblem::title::TitleScene;
+use std::path::PathBuf;
+
+/// Compares a frame against `tests/snapshots/<name>.png`.
+///
+/// A missing or mismatched snapshot fails, and the frame is written to `<name>.new.png` beside it
+/// for inspection. Set UPDATE_SNAPSHOTS=1 to write the frame to `<name>.png` instead.
+fn assert_snapshot(name: &str, frame: &Frame) {
+	let mut png = Vec::new();
+	frame.write_png(|bytes| png.extend_from_slice(bytes));
+
+	let directory = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/snapshots");
+	let path = directory.join(format!("{name}.png"));
+	if std::env::var_os("UPDATE_SNAPSHOTS").is_some() {
+		std::fs::create_dir_all(&directory).unwrap();
+		std::fs::write(&path, &png).unwrap();
+		eprintln!("wrote {}", path.display());
+		return;
+	}
+
+	let problem = match std::fs::read(&path) {
+		Ok(expected) if expected == png => return,
+		Ok(_) => "does not match",
+		Err(_) => "is missing",
+	};
+	let new = directory.join(format!("{name}.new.png"));
+	std::fs::create_dir_all(&directory).unwrap();
+	std::fs::write(&new, &png).unwrap();
+	panic!(
+		"{} {problem}; see {}, and run with UPDATE_SNAPSHOTS=1 to accept it",
+		path.display(),
+		new.display()
+	);
+}
+
+/// Enters a scene and draws its first frame.
+fn render<'a, S: Scene<'a, &'a SoftwarePpu>>(ppu: &'a SoftwarePpu, scene: &mut S) -> Frame {
+	let mut oam = Oam::new();
+	scene.enter(&mut Vram::new(ppu));
+	oam.clean();
+	scene.draw(&mut oam);
+	oam.commit(&ppu);
+	ppu.render()
+}
+
+#[test]
+fn title_screen() {
+	let ppu = SoftwarePpu::new();
+	let frame = render(&ppu, &mut TitleScene::new(&ppu));
+	assert_snapshot("title", &frame);
+}
+
+#[test]
+fn map() {
+	let ppu = SoftwarePpu::new();
+	let frame = render(&ppu, &mut GameState::new(&ppu, &DEBUG_MAP).unwrap());
+	assert_snapshot("map", &frame);
+}
+
+#[test]
+fn units_and_cursor_are_drawn_over_the_map() {
+	let ppu = SoftwarePpu::new();
+	let with_sprites = render(&ppu, &mut GameState::new(&ppu, &DEBUG_MAP).unwrap());
+
+	// Hide every sprite, leaving only the backgrounds.
+	let mut oam = Oam::new();
+	oam.clean();
+	oam.commit(&&ppu);
+	let without_sprites = ppu.render();
+
+	let differs = |left: usize, top: usize| {
+		(top..top + 16).any(|y| (left..left + 16).any(|x| with_sprites.pixels[y][x] != without_sprites.pixels[y][x]))
+	};
+	// The cursor starts in the top-left corner, and Luvui starts at (1, 2).
+	assert!(differs(0, 0));
+	assert!(differs(16, 32));
+	// Nothing stands at (5, 5).
+	assert!(!differs(80, 80));
+}
@@ -0,0 +1,178 @@
+use furry_emblem::campaign::{ChapterIntro, NAMES};
+use furry_emblem::console::{Input, KeySource, Keys};
+use furry_emblem::replay::{Playback, Recorder, ReplayHeader};
+use furry_emblem::rng::Rng;
+use furry_emblem::save::SaveError;
+use furry_emblem::scene::{SceneKind, SceneStack};
+use furry_emblem::transform::Vector2D;
+use furry_emblem::unit::UnitList;
+use furry_emblem::video::NullVideo;
+
+/// Holds each key for a number of frames.
+struct Script {
+	frames: Vec<Keys>,
+}
+
+impl Script {
+	fn new(holds: &[(Keys, usize)]) -> Self {
+		let mut frames: Vec<Keys> = holds
+			.iter()
+			.flat_map(|(keys, frames)| std::iter::repeat_n(*keys, *frames))
+			.collect();
+		frames.reverse();
+		Self { frames }
+	}
+}
+
+impl KeySource for Script {
+	fn read(&mut self) -> Keys {
+		self.frames.pop().unwrap_or(Keys::NONE)
+	}
+}
+
+/// Everything which should come out the same when a session is replayed.
+type Outcome = (Vec<(String, Vector2D<i16>, u8)>, u16, Rng);
+
+fn header(chapter: u8, seed: u32, frame: u32) -> ReplayHeader {
+	ReplayHeader {
+		chapter,
+		rng: Rng::new(seed),
+		frame,
+		held: Keys::NONE,
+	}
+}
+
+/// Plays a chapter from its introduction for a number of frames, starting from `header`.
+fn play(header: ReplayHeader, roster: &UnitList<'static>, source: &mut impl KeySource, frames: usize) -> Outcome {
+	let mut scenes = SceneStack::empty(NullVideo);
+	for (_, unit) in roster.iter() {
+		scenes.scenes_mut().roster.push(unit.clone()).unwrap();
+	}
+	scenes.scenes_mut().intro = Some(ChapterIntro::new(NullVideo, header.chapter, header.rng));
+	scenes.push(|_| SceneKind::Intro);
+	scenes.apply_transition();
+	let mut input = Input::new();
+	input.frame = header.frame;
+	input.held = header.held;
+	for _ in 0..frames {
+		input.update(source);
+		scenes.tick(&input);
+		scenes.apply_transition();
+	}
+
+	let (Some(SceneKind::Map), Some(game)) = (scenes.current(), &scenes.scenes().map) else {
+		panic!("the chapter never began");
+	};
+	let units = game
+		.units()
+		.iter()
+		.map(|(_, unit)| (unit.name.to_string(), unit.position, unit.hp))
+		.collect();
+	(units, game.turn().turn, game.rng)
+}
+
+#[test]
+fn replays_match_the_recording() {
+	let header = header(0, 1234, 77);
+	// Begin the chapter, walk Luvui towards the enemy, wait, then let the enemy phase play out.
+	let script = Script::new(&[
+		(Keys::A, 1),
+		(Keys::NONE, 5),
+		(Keys::RIGHT, 1),
+		(Keys::NONE, 1),
+		(Keys::DOWN, 20),
+		(Keys::NONE, 1),
+		(Keys::UP, 1),
+		(Keys::A, 2),
+		(Keys::RIGHT, 30),
+		(Keys::A, 1),
+		(Keys::NONE, 3),
+		(Keys::DOWN, 1),
+		(Keys::NONE, 1),
+		(Keys::A, 1),
+	]);
+	let mut buffer = [0; 1024];
+	let mut recorder = Recorder::new(script, &mut buffer);
+	recorder.restart(&header, &UnitList::new());
+	let recorded = play(header, &UnitList::new(), &mut recorder, 600);
+	let replay = recorder.finish().unwrap().to_vec();
+
+	let mut roster = UnitList::new();
+	let (replayed_header, mut playback) = Playback::new(&replay, NAMES, &mut roster).unwrap();
+	assert_eq!(replayed_header, header);
+	assert_eq!(roster.len(), 0);
+	assert_eq!(play(replayed_header, &roster, &mut playback, 600), recorded);
+	assert!(playback.is_finished());
+}
+
+#[test]
+fn long_holds_are_split_into_runs() {
+	let keys = [(Keys::A | Keys::B, 200), (Keys::NONE, 1), (Keys::L, 64)];
+	let mut buffer = [0; 64];
+	let mut recorder = Recorder::new(Script::new(&keys), &mut buffer);
+	recorder.restart(&header(0, 1, 0), &UnitList::new());
+	for _ in 0..265 {
+		recorder.read();
+	}
+	let replay = recorder.finish().unwrap().to_vec();
+
+	let (_, mut playback) = Playback::new(&replay, NAMES, &mut UnitList::new()).unwrap();
+	for (held, frames) in keys {
+		for _ in 0..frames {
+			assert_eq!(playback.read(), held);
+		}
+	}
+	assert!(playback.is_finished());
+	assert_eq!(playback.read(), Keys::NONE);
+}
+
+#[test]
+fn full_recordings_keep_the_start_of_the_chapter() {
+	let keys = [(Keys::A, 1), (Keys::B, 1), (Keys::A, 1), (Keys::B, 1)];
+	let mut buffer = [0; 64];
+	let mut recorder = Recorder::new(Script::new(&keys), &mut buffer);
+	recorder.restart(&header(0, 1, 0), &UnitList::new());
+	let header_size = recorder.finish().unwrap().len();
+
+	// Leave room for two runs, but not a third.
+	let mut small = vec![0; header_size + 5];
+	let mut recorder = Recorder::new(Script::new(&keys), &mut small);
+	recorder.restart(&header(0, 1, 0), &UnitList::new());
+	for _ in 0..keys.len() {
+		recorder.read();
+	}
+	let replay = recorder.finish().unwrap().to_vec();
+	assert!(recorder.is_full());
+	assert_eq!(replay.len(), header_size + 4);
+
+	// What was kept still plays back, and the keypad would take over from there.
+	let (_, mut playback) = Playback::new(&replay, NAMES, &mut UnitList::new()).unwrap();
+	assert_eq!(playback.read(), Keys::A);
+	assert_eq!(playback.read(), Keys::B);
+	assert!(playback.is_finished());
+
+	// The next chapter starts over with an empty buffer.
+	recorder.restart(&header(1, 2, 500), &UnitList::new());
+	assert!(!recorder.is_full());
+	let replay = recorder.finish().unwrap().to_vec();
+	let (replayed_header, playback) = Playback::new(&replay, NAMES, &mut UnitList::new()).unwrap();
+	assert_eq!(replayed_header, header(1, 2, 500));
+	assert!(playback.is_finished());
+}
+
+#[test]
+fn recordings_only_start_with_a_chapter() {
+	let mut buffer = [0; 64];
+	let mut recorder = Recorder::new(Script::new(&[(Keys::A, 10)]), &mut buffer);
+	for _ in 0..10 {
+		recorder.read();
+	}
+	assert_eq!(recorder.finish().err(), Some(SaveError::Empty));
+
+	// A header which doesn't fit can't be saved at all.
+	let mut tiny = [0; 4];
+	let mut recorder = Recorder::new(Script::new(&[]), &mut tiny);
+	recorder.restart(&header(0, 1, 0), &UnitList::new());
+	assert!(recorder.is_full());
+	assert_eq!(recorder.finish().err(), Some(SaveError::TooLarge));
+}
@@ -0,0 +1,258 @@
+use furry_emblem::campaign::{CAMPAIGN_SLOT, CHAPTERS, NAMES};
+use furry_emblem::console::{Input, KeySource, Keys, Vram};
+use furry_emblem::game::GameState;
+use furry_emblem::rng::Rng;
+use furry_emblem::save::*;
+use furry_emblem::scene::{Scene, SceneKind, SceneStack};
+use furry_emblem::title;
+use furry_emblem::unit::{Faction, Unit, UnitList};
+use furry_emblem::video::NullVideo;
+
+/// Never presses anything.
+struct Idle;
+
+impl KeySource for Idle {
+	fn read(&mut self) -> Keys {
+		Keys::NONE
+	}
+}
+
+/// Presses its keys once, then nothing.
+struct Script(Option<Keys>);
+
+impl KeySource for Script {
+	fn read(&mut self) -> Keys {
+		self.0.take().unwrap_or(Keys::NONE)
+	}
+}
+
+fn sram() -> Vec<u8> {
+	vec![0; SRAM_SIZE]
+}
+
+/// Starts the first chapter with a wounded lord, then lets a round of turns play out.
+fn game_in_progress() -> GameState<'static, NullVideo> {
+	let level = CHAPTERS[0].map;
+	let lord = level.units.iter().find(|unit| unit.faction == Faction::Player).unwrap();
+	let mut wounded = Unit::new(lord);
+	wounded.hp -= 1;
+	wounded.exp = 42;
+	let mut roster = UnitList::new();
+	roster.push(wounded).unwrap();
+
+	let mut game = GameState::with_roster(NullVideo, level, &roster, |_| false).unwrap();
+	game.enter(&mut Vram::new(NullVideo));
+
+	let mut input = Input::new();
+	for _ in 0..10_000 {
+		if game.turn().turn == 2 && game.turn().phase == Faction::Player {
+			break;
+		}
+		// Start ends the player's phase; the computer takes its turns on its own.
+		let keys = if game.turn().phase == Faction::Player { Keys::START } else { Keys::NONE };
+		input.update(&mut Script(Some(keys)));
+		game.tick(&input);
+		input.update(&mut Idle);
+		game.tick(&input);
+	}
+	assert_eq!(game.turn().turn, 2);
+	game
+}
+
+fn assert_same_units(a: &UnitList, b: &UnitList) {
+	assert_eq!(a.len(), b.len());
+	for ((i, a), (j, b)) in a.iter().zip(b.iter()) {
+		assert_eq!(i, j);
+		assert_eq!(a.name, b.name);
+		assert_eq!(a.faction, b.faction);
+		assert_eq!(a.position, b.position);
+		assert_eq!(a.hp, b.hp);
+		assert_eq!(a.stats, b.stats);
+		assert_eq!(a.level, b.level);
+		assert_eq!(a.exp, b.exp);
+		assert_eq!(a.inventory, b.inventory);
+		assert_eq!(a.has_acted, b.has_acted);
+	}
+}
+
+#[test]
+fn suspended_chapters_resume_where_they_left_off() {
+	let game = game_in_progress();
+	let mut sram = sram();
+	suspend(&mut sram[..], 0, &game).unwrap();
+
+	assert_eq!(suspended_chapter(&sram[..]), Ok(0));
+	let resumed = resume(&sram[..], NullVideo, CHAPTERS[0].map, NAMES).unwrap();
+	assert_eq!(resumed.chapter, Some(0));
+	assert_eq!(resumed.turn().turn, game.turn().turn);
+	assert_eq!(resumed.turn().phase, game.turn().phase);
+	assert_eq!(resumed.cursor_position(), game.cursor_position());
+	assert_same_units(resumed.units(), game.units());
+}
+
+#[test]
+fn campaign_saves_round_trip() {
+	let game = game_in_progress();
+	let mut sram = sram();
+	let save = CampaignSave {
+		chapter: 1,
+		rng: Rng::new(1234),
+	};
+	let mut roster = UnitList::new();
+	game.survivors(&mut roster);
+	save_campaign(&mut sram[..], 2, &save, &roster).unwrap();
+
+	let mut loaded_roster = UnitList::new();
+	let loaded = load_campaign(&sram[..], 2, NAMES, &mut loaded_roster).unwrap();
+	assert_eq!(loaded.chapter, 1);
+	assert_eq!(loaded.rng.to_bytes(), save.rng.to_bytes());
+	assert_same_units(&loaded_roster, &roster);
+
+	assert_eq!(load_campaign(&sram[..], 0, NAMES, &mut loaded_roster).err(), Some(SaveError::Empty));
+	assert_eq!(
+		load_campaign(&sram[..], SAVE_SLOTS, NAMES, &mut loaded_roster).err(),
+		Some(SaveError::NoSuchSlot)
+	);
+}
+
+#[test]
+fn corrupted_bytes_fail_the_checksum() {
+	let game = game_in_progress();
+	let mut sram = sram();
+	suspend(&mut sram[..], 0, &game).unwrap();
+
+	// Flip one bit somewhere in the payload, past the header.
+	sram[SUSPEND_SLOT * SLOT_SIZE + 40] ^= 0x10;
+	assert_eq!(suspended_chapter(&sram[..]), Err(SaveError::Corrupt));
+	assert_eq!(
+		resume(&sram[..], NullVideo, CHAPTERS[0].map, NAMES).err(),
+		Some(SaveError::Corrupt)
+	);
+}
+
+#[test]
+fn other_versions_and_kinds_are_rejected() {
+	let mut sram = sram();
+	let mut buffer = [0; PAYLOAD_SIZE];
+	write_slot(&mut sram[..], 0, SaveKind::Campaign, &[1, 2, 3]).unwrap();
+
+	assert_eq!(
+		read_slot(&sram[..], 0, SaveKind::Suspend, &mut buffer).err(),
+		Some(SaveError::WrongKind)
+	);
+	assert_eq!(
+		read_slot(&sram[..], 0, SaveKind::Campaign, &mut buffer).unwrap().rest(),
+		[1, 2, 3]
+	);
+
+	// The version follows the four byte magic.
+	let old = SAVE_VERSION - 1;
+	sram[4..6].copy_from_slice(&old.to_le_bytes());
+	assert_eq!(
+		read_slot(&sram[..], 0, SaveKind::Campaign, &mut buffer).err(),
+		Some(SaveError::Version(old))
+	);
+}
+
+#[test]
+fn oversized_payloads_are_refused() {
+	let mut sram = sram();
+	write_slot(&mut sram[..], 1, SaveKind::Campaign, &[7; 16]).unwrap();
+
+	assert_eq!(
+		write_slot(&mut sram[..], 1, SaveKind::Campaign, &[0; PAYLOAD_SIZE + 1]),
+		Err(SaveError::TooLarge)
+	);
+	// The slot is left as it was.
+	let mut buffer = [0; PAYLOAD_SIZE];
+	assert_eq!(
+		read_slot(&sram[..], 1, SaveKind::Campaign, &mut buffer).unwrap().rest(),
+		[7; 16]
+	);
+	assert!(write_slot(&mut sram[..], 1, SaveKind::Campaign, &[0; PAYLOAD_SIZE]).is_ok());
+
+	let mut writer = Writer::new();
+	writer.bytes(&[0; PAYLOAD_SIZE]);
+	assert!(writer.finish().is_ok());
+	writer.u8(0);
+	assert_eq!(writer.finish().err(), Some(SaveError::TooLarge));
+}
+
+/// Starts at the title screen, saving to `sram`.
+fn title_screen(sram: &mut [u8; SRAM_SIZE]) -> SceneStack<'_, NullVideo> {
+	let mut scenes = SceneStack::empty(NullVideo);
+	scenes.scenes_mut().storage = Some(sram);
+	scenes.push(title::show_title);
+	scenes.apply_transition();
+	scenes
+}
+
+/// Presses `keys` for a frame and lets go the next, applying any transitions.
+fn press(scenes: &mut SceneStack<NullVideo>, input: &mut Input, keys: Keys) {
+	for keys in [keys, Keys::NONE] {
+		input.update(&mut Script(Some(keys)));
+		scenes.tick(input);
+		scenes.apply_transition();
+	}
+}
+
+#[test]
+fn suspending_returns_to_the_title_screen_which_continues_the_chapter() {
+	let mut sram = [0; SRAM_SIZE];
+	let mut scenes = title_screen(&mut sram);
+	let mut input = Input::new();
+
+	press(&mut scenes, &mut input, Keys::START);
+	assert_eq!(scenes.current(), Some(SceneKind::Intro));
+	press(&mut scenes, &mut input, Keys::A);
+	assert_eq!(scenes.current(), Some(SceneKind::Map));
+
+	// The cursor starts on an empty tile, where A opens the map menu. Suspend is its first option.
+	press(&mut scenes, &mut input, Keys::A);
+	press(&mut scenes, &mut input, Keys::A);
+	assert_eq!(scenes.current(), Some(SceneKind::Title));
+	assert!(scenes.scenes().map.is_none());
+	assert_eq!(suspended_chapter(scenes.scenes().storage.as_deref().unwrap()), Ok(0));
+
+	// Continue is below New Game.
+	press(&mut scenes, &mut input, Keys::DOWN);
+	press(&mut scenes, &mut input, Keys::A);
+	assert_eq!(scenes.current(), Some(SceneKind::Map));
+	assert_eq!(scenes.scenes().map.as_ref().unwrap().chapter, Some(0));
+}
+
+#[test]
+fn continuing_without_a_suspend_introduces_the_saved_chapter() {
+	let mut sram = [0; SRAM_SIZE];
+	let save = CampaignSave {
+		chapter: 1,
+		rng: Rng::new(1234),
+	};
+	let mut roster = UnitList::new();
+	game_in_progress().survivors(&mut roster);
+	save_campaign(&mut sram[..], CAMPAIGN_SLOT, &save, &roster).unwrap();
+	let mut scenes = title_screen(&mut sram);
+	let mut input = Input::new();
+
+	press(&mut scenes, &mut input, Keys::DOWN);
+	press(&mut scenes, &mut input, Keys::A);
+	assert_eq!(scenes.current(), Some(SceneKind::Intro));
+	let intro = scenes.scenes().intro.as_ref().unwrap();
+	assert_eq!(intro.chapter(), 1);
+	assert_eq!(intro.rng.to_bytes(), save.rng.to_bytes());
+	assert_same_units(&scenes.scenes().roster, &roster);
+}
+
+#[test]
+fn the_title_screen_only_offers_to_continue_when_there_is_a_save() {
+	let mut sram = [0; SRAM_SIZE];
+	let mut scenes = title_screen(&mut sram);
+	let mut input = Input::new();
+
+	// With only one option, down does nothing and A starts a new game.
+	press(&mut scenes, &mut input, Keys::DOWN);
+	press(&mut scenes, &mut input, Keys::A);
+	let intro = scenes.scenes().intro.as_ref().unwrap();
+	assert_eq!(intro.chapter(), 0);
+	assert!(scenes.scenes().roster.is_empty());
+}